@@ -0,0 +1,52 @@
+#[cfg(feature = "bitmask")]
+use aoc_2022::day06::bitmask_first_marker;
+#[cfg(feature = "parallel")]
+use aoc_2022::day06::parallel_first_marker;
+use aoc_2022::day06::{find_marker, get_start_of_marker_for_signal, RingBuffer};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIGNAL_LEN: usize = 1_000_000;
+const RUNS: u32 = 5;
+
+// A signal whose first message marker is at its end, so the whole of it has to be scanned
+fn signal() -> String {
+    let mut signal: String = "abcdefghijklm".chars().cycle().take(SIGNAL_LEN).collect();
+    signal.push_str("nopqrstuvwxyz");
+    signal
+}
+
+fn bench<F: Fn() -> usize>(name: &str, f: F) {
+    let mut total = Duration::ZERO;
+    let mut res = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        res = black_box(f());
+        total += start.elapsed();
+    }
+    println!(
+        "{:<32} {:>10.2?} per run (marker at {})",
+        name,
+        total / RUNS,
+        res
+    );
+}
+
+fn main() {
+    let signal = signal();
+
+    bench("ring buffer (window 14)", || {
+        let mut buffer = RingBuffer::<char, 14>::new();
+        get_start_of_marker_for_signal(&signal, &mut buffer)
+    });
+    bench("marker detector (window 14)", || find_marker(&signal, 14));
+    #[cfg(feature = "bitmask")]
+    bench("bitmask (window 14)", || {
+        bitmask_first_marker(&signal, 14).unwrap_or(signal.len())
+    });
+    #[cfg(feature = "parallel")]
+    bench("parallel bitmask (window 14)", || {
+        let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+        parallel_first_marker(&signal, 14, threads).unwrap_or(signal.len())
+    });
+}
@@ -0,0 +1,66 @@
+use aoc_2022::day04::{
+    parse_input_with, parse_section_range, IndexedSection, ParseOptions, SectionIndex,
+};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+use std::process;
+
+const DEFAULT_INPUT: &str = "input/2022/day4.txt";
+
+fn parse_section(raw: &str) -> Option<usize> {
+    raw.trim().parse::<usize>().ok()
+}
+
+fn print_results(results: &[&IndexedSection]) {
+    for x in results {
+        println!(
+            "line {} elf {}: {}-{}",
+            x.line,
+            x.elf,
+            x.section.start(),
+            x.section.end()
+        );
+    }
+    println!("{} assignment(s)", results.len());
+}
+
+// Reads queries from stdin, one per line:
+//   overlap <start>-<end>
+//   contains <section>
+fn main() {
+    let path = env::args().nth(1).unwrap_or(DEFAULT_INPUT.to_string());
+    let input = fs::read_to_string(&path).expect("could not read input file");
+    let groups = match parse_input_with(&input, &ParseOptions::default()) {
+        Ok(x) => x,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        }
+    };
+    let index = SectionIndex::new(&groups);
+    eprintln!("{} assignments indexed from {}", index.len(), path);
+
+    for (i, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.expect("could not read query");
+        let mut tokens = line.split_whitespace();
+        match (tokens.next(), tokens.next()) {
+            // ranges are checked like the input, so reversed ones are rejected
+            (Some("overlap"), Some(range)) => {
+                match parse_section_range(range, i + 1, &ParseOptions::default()) {
+                    Ok(range) => print_results(&index.overlapping(range)),
+                    Err(err) => eprintln!("query {}", err),
+                }
+            }
+            (Some("contains"), Some(section)) => match parse_section(section) {
+                Some(x) => print_results(&index.containing(x)),
+                None => eprintln!("invalid section: {}", section),
+            },
+            (None, _) => {}
+            _ => eprintln!(
+                "unknown query: {} (use `overlap A-B` or `contains S`)",
+                line
+            ),
+        }
+    }
+}
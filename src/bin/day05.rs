@@ -0,0 +1,139 @@
+use aoc_2022::day05::{animate, crane_from_name, try_parse_input, Frame, InvalidMovePolicy, Ship};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_INPUT: &str = "input/2022/day5.txt";
+const DEFAULT_CRANE: &str = "9000";
+const DEFAULT_DELAY_MS: u64 = 200;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: day05 [--crane 9000|9001|9000-bulk|limited:<capacity>|inverting] [--animate <every>] \
+         [--delay <ms>] [--output <file>] [--svg <dir>] [--on-invalid fail|skip|clamp] [input]"
+    );
+    process::exit(1);
+}
+
+fn parse_number<T: std::str::FromStr>(raw: Option<String>) -> T {
+    raw.and_then(|x| x.parse::<T>().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn frame_title(frame: &Frame) -> String {
+    format!("after move {}", frame.moves_applied)
+}
+
+fn main() {
+    let mut crane_name = DEFAULT_CRANE.to_string();
+    let mut path = DEFAULT_INPUT.to_string();
+    let mut every: Option<usize> = None;
+    let mut delay = DEFAULT_DELAY_MS;
+    let mut output: Option<String> = None;
+    let mut svg_dir: Option<String> = None;
+    let mut policy = InvalidMovePolicy::Fail;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crane" => crane_name = args.next().unwrap_or_else(|| usage()),
+            "--animate" => every = Some(parse_number(args.next())),
+            "--delay" => delay = parse_number(args.next()),
+            "--output" => output = Some(args.next().unwrap_or_else(|| usage())),
+            "--svg" => svg_dir = Some(args.next().unwrap_or_else(|| usage())),
+            "--on-invalid" => {
+                policy = match args.next().as_deref() {
+                    Some("fail") => InvalidMovePolicy::Fail,
+                    Some("skip") => InvalidMovePolicy::Skip,
+                    Some("clamp") => InvalidMovePolicy::Clamp,
+                    _ => usage(),
+                }
+            }
+            "-h" | "--help" => usage(),
+            _ => path = arg,
+        }
+    }
+
+    let mut crane = crane_from_name(&crane_name).unwrap_or_else(|| {
+        eprintln!("unknown crane model: {}", crane_name);
+        usage()
+    });
+    let input = try_parse_input(&fs::read_to_string(&path).expect("could not read input file"))
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+    if every.is_none() && output.is_none() && svg_dir.is_none() {
+        let mut ship = Ship::new(input.0);
+        match ship.try_operate(crane.as_mut(), &input.1, policy) {
+            Ok(errors) => {
+                for err in errors {
+                    eprintln!("{}", err);
+                }
+                println!("{}", ship.top_cargo());
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut dump = output
+        .as_ref()
+        .map(|file| BufWriter::new(File::create(file).expect("could not create output file")));
+    if let Some(dir) = &svg_dir {
+        fs::create_dir_all(dir).expect("could not create SVG directory");
+    }
+
+    let frames = animate(
+        &input.0,
+        &input.1,
+        crane.as_mut(),
+        every.unwrap_or(1),
+        policy,
+    );
+    for (i, frame) in frames.enumerate() {
+        let frame = frame.unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        for err in &frame.errors {
+            eprintln!("{}", err);
+        }
+
+        if let Some(dump) = dump.as_mut() {
+            let separator = if i == 0 { "" } else { "\n" };
+            write!(
+                dump,
+                "{}{}\n{}\n",
+                separator,
+                frame_title(&frame),
+                frame.drawing()
+            )
+            .expect("could not write output file");
+        }
+
+        if let Some(dir) = &svg_dir {
+            let file = Path::new(dir).join(format!("frame_{:05}.svg", i));
+            fs::write(file, frame.svg()).expect("could not write SVG frame");
+        }
+
+        if output.is_none() && svg_dir.is_none() {
+            // clear the terminal and move the cursor home before every frame
+            print!("\x1b[2J\x1b[H");
+            println!("{}\n{}", frame_title(&frame), frame.drawing());
+            thread::sleep(Duration::from_millis(delay));
+        }
+    }
+
+    if let Some(mut dump) = dump {
+        dump.flush().expect("could not write output file");
+    }
+}
@@ -0,0 +1,97 @@
+use aoc_2022::day06::{decode, Alphabet, MarkerSpec};
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+const DEFAULT_INPUT: &str = "input/2022/day6.txt";
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: day06 [--window <size>|<min>..=<max>|<min>-<max>] \
+         [--alphabet lowercase|bytes|unicode|custom:<symbols>] [input|-]"
+    );
+    process::exit(1);
+}
+
+fn parse_windows(raw: &str) -> Option<Vec<usize>> {
+    match raw.split_once("..=").or_else(|| raw.split_once('-')) {
+        Some((min, max)) => {
+            let (min, max): (usize, usize) = (min.parse().ok()?, max.parse().ok()?);
+            // a reversed range would silently check nothing
+            if min > max {
+                return None;
+            }
+            Some((min..=max).collect())
+        }
+        None => Some(vec![raw.parse().ok()?]),
+    }
+}
+
+fn main() {
+    let mut windows = vec![4, 14];
+    let mut alphabet = Alphabet::Lowercase;
+    let mut custom_spec = false;
+    let mut path = DEFAULT_INPUT.to_string();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => {
+                windows = args
+                    .next()
+                    .and_then(|x| parse_windows(&x))
+                    .unwrap_or_else(|| usage());
+                custom_spec = true;
+            }
+            "--alphabet" => {
+                alphabet = args
+                    .next()
+                    .unwrap_or_else(|| usage())
+                    .parse()
+                    .unwrap_or_else(|err| {
+                        eprintln!("{}", err);
+                        usage()
+                    });
+                custom_spec = true;
+            }
+            "-h" | "--help" => usage(),
+            _ => path = arg,
+        }
+    }
+
+    // `-` streams stdin through the byte decoder instead of loading the whole signal. It only
+    // knows the puzzle's packet and message markers.
+    if path == "-" {
+        if custom_spec {
+            eprintln!("--window and --alphabet can't be used when reading from stdin");
+            usage();
+        }
+        let decoder = decode(io::stdin().lock()).expect("could not read stdin");
+        for (window, marker) in windows
+            .iter()
+            .zip([decoder.start_of_packet(), decoder.start_of_message()])
+        {
+            match marker {
+                Some(position) => println!("window {}: marker at {}", window, position),
+                None => println!("window {}: no marker", window),
+            }
+        }
+        return;
+    }
+
+    let input = fs::read_to_string(&path).expect("could not read input file");
+    let signal = input.trim_end_matches('\n');
+
+    for window in windows {
+        let res = MarkerSpec::new(window, alphabet.clone()).and_then(|x| x.first_marker(signal));
+        match res {
+            Ok(Some(position)) => println!("window {}: marker at {}", window, position),
+            Ok(None) => println!("window {}: no marker", window),
+            Err(err) => {
+                eprintln!("window {}: {}", window, err);
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -18,7 +18,7 @@ pub fn parse_input(input: &str) -> Vec<Elf> {
     let mut res: Vec<Elf> = vec![];
     let mut buffer: Elf = vec![];
 
-    for line in input.lines().map(|x| x.trim()).into_iter() {
+    for line in input.lines().map(|x| x.trim()) {
         if line.is_empty() {
             res.push(buffer);
             buffer = vec![];
@@ -27,21 +27,17 @@ pub fn parse_input(input: &str) -> Vec<Elf> {
         }
     }
 
-    return res;
+    res
 }
 
 #[aoc(day1, part1)]
 pub fn solve_part1(input: &[Elf]) -> u32 {
-    input
-        .into_iter()
-        .map(|x| x.total_calories())
-        .max()
-        .unwrap_or(0)
+    input.iter().map(|x| x.total_calories()).max().unwrap_or(0)
 }
 
 #[aoc(day1, part2)]
 pub fn solve_part2(input: &[Elf]) -> u32 {
-    let mut calories_by_elf: Vec<u32> = input.into_iter().map(|x| x.total_calories()).collect();
+    let mut calories_by_elf: Vec<u32> = input.iter().map(|x| x.total_calories()).collect();
 
     const N: u32 = 3;
     let mut top_calories: Vec<u32> = vec![];
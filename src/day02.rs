@@ -101,7 +101,6 @@ type RoundWithOutcome = (Choice, Outcome);
 pub fn parse_input_part1(input: &str) -> Vec<RoundWithChoices> {
     input
         .lines()
-        .into_iter()
         .map(|line| match line.split(' ').collect::<Vec<&str>>()[..] {
             [opponent, player] => (Choice::from(player), Choice::from(opponent)),
             _ => panic!("Each round must contain exactly two plays"),
@@ -113,7 +112,6 @@ pub fn parse_input_part1(input: &str) -> Vec<RoundWithChoices> {
 pub fn parse_input_part2(input: &str) -> Vec<RoundWithOutcome> {
     input
         .lines()
-        .into_iter()
         .map(|line| match line.split(' ').collect::<Vec<&str>>()[..] {
             [opponent, outcome] => (Choice::from(opponent), Outcome::from(outcome)),
             _ => panic!("Each round must contain exactly two plays"),
@@ -123,13 +121,13 @@ pub fn parse_input_part2(input: &str) -> Vec<RoundWithOutcome> {
 
 #[aoc(day2, part1)]
 pub fn solve_part1(input: &[RoundWithChoices]) -> u64 {
-    input.into_iter().map(|x| (x.0).score_versus(x.1)).sum()
+    input.iter().map(|x| (x.0).score_versus(x.1)).sum()
 }
 
 #[aoc(day2, part2)]
 pub fn solve_part2(input: &[RoundWithOutcome]) -> u64 {
     input
-        .into_iter()
+        .iter()
         .map(|&x| (Choice::from(x), x.0))
         .map(|x| (x.0).score_versus(x.1))
         .sum()
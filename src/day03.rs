@@ -2,21 +2,137 @@ use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
 use itertools::Itertools;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::convert::From;
+use std::fmt;
+use std::str::FromStr;
 
-type Item = char;
+pub type Item = char;
 
-trait Prioritizable {
-    fn priority(&self) -> u32;
+const GROUP_SIZE: usize = 3;
+
+pub trait PriorityScheme {
+    fn priority(&self, item: Item) -> Option<u32>;
+}
+
+// a→1..z→26, A→27..Z→52, as described in the puzzle
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct DefaultScheme;
+
+impl PriorityScheme for DefaultScheme {
+    fn priority(&self, item: Item) -> Option<u32> {
+        match item {
+            'a'..='z' => Some((item as u32) - 96),
+            'A'..='Z' => Some((item as u32) - 38),
+            _ => None,
+        }
+    }
 }
 
-impl Prioritizable for Item {
-    fn priority(&self) -> u32 {
+// Same as the default scheme for ASCII letters. Any other visible character gets a priority
+// above 52 derived from its Unicode scalar value, so priorities never collide.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct UnicodeScheme;
+
+impl PriorityScheme for UnicodeScheme {
+    fn priority(&self, item: Item) -> Option<u32> {
+        if item.is_whitespace() || item.is_control() {
+            return None;
+        }
+        DefaultScheme.priority(item).or(Some(52 + (item as u32)))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TableScheme {
+    priorities: HashMap<Item, u32>,
+}
+
+impl TableScheme {
+    // Each item gets its (1-based) position in `order` as priority
+    pub fn from_order(order: &str) -> Self {
+        Self {
+            priorities: order
+                .chars()
+                .enumerate()
+                .map(|(i, item)| (item, i as u32 + 1))
+                .collect(),
+        }
+    }
+}
+
+impl PriorityScheme for TableScheme {
+    fn priority(&self, item: Item) -> Option<u32> {
+        self.priorities.get(&item).copied()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TableError {
+    MissingPriority { line: usize },
+    InvalidItem { line: usize, token: String },
+    InvalidPriority { line: usize, token: String },
+    DuplicateItem { line: usize, item: Item },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            'a'..='z' => (*self as u32) - 96,
-            'A'..='Z' => (*self as u32) - 38,
-            _ => unreachable!(),
+            Self::MissingPriority { line } => write!(f, "line {}: missing priority", line),
+            Self::InvalidItem { line, token } => {
+                write!(f, "line {}: invalid item {:?}", line, token)
+            }
+            Self::InvalidPriority { line, token } => {
+                write!(f, "line {}: invalid priority {:?}", line, token)
+            }
+            Self::DuplicateItem { line, item } => {
+                write!(f, "line {}: duplicate item {:?}", line, item)
+            }
+        }
+    }
+}
+
+// Parses a table with one `<item> <priority>` entry per line. Blank lines are ignored.
+impl FromStr for TableScheme {
+    type Err = TableError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let mut priorities = HashMap::new();
+
+        for (i, entry) in raw.lines().enumerate() {
+            let line = i + 1;
+            let mut tokens = entry.split_whitespace();
+            let item_token = match tokens.next() {
+                Some(x) => x,
+                None => continue,
+            };
+            let priority_token = tokens.next().ok_or(TableError::MissingPriority { line })?;
+
+            let mut chars = item_token.chars();
+            let item = match (chars.next(), chars.next()) {
+                (Some(x), None) => x,
+                _ => {
+                    return Err(TableError::InvalidItem {
+                        line,
+                        token: item_token.to_string(),
+                    })
+                }
+            };
+            let priority =
+                priority_token
+                    .parse::<u32>()
+                    .map_err(|_| TableError::InvalidPriority {
+                        line,
+                        token: priority_token.to_string(),
+                    })?;
+
+            if priorities.insert(item, priority).is_some() {
+                return Err(TableError::DuplicateItem { line, item });
+            }
         }
+
+        Ok(Self { priorities })
     }
 }
 
@@ -37,7 +153,7 @@ impl Rucksack {
     fn compartments(&self) -> (Rucksack, Rucksack) {
         let left = self.items[0..self.items.len() / 2].to_owned();
         let right = self.items[self.items.len() / 2..self.items.len()].to_owned();
-        return (Rucksack { items: left }, Rucksack { items: right });
+        (Rucksack { items: left }, Rucksack { items: right })
     }
 
     fn common_items(&self, other: &Rucksack) -> Vec<Item> {
@@ -57,7 +173,7 @@ impl Rucksack {
 
     pub fn common_item_among(list: &[Rucksack]) -> Item {
         let first = list[0].clone();
-        let others: Vec<Rucksack> = list[1..list.len()].iter().map(|x| x.clone()).collect();
+        let others: Vec<Rucksack> = list[1..list.len()].to_vec();
 
         let common = others.into_iter().fold(first, |res, rucksack| Rucksack {
             items: rucksack.common_items(&res).to_owned(),
@@ -65,42 +181,651 @@ impl Rucksack {
 
         common.items[0]
     }
+
+    fn unique_items(&self) -> Vec<Item> {
+        self.items.iter().copied().unique().sorted().collect()
+    }
+
+    fn shared_items_in_compartments(&self) -> Vec<Item> {
+        let pockets = self.compartments();
+        pockets
+            .0
+            .unique_items()
+            .into_iter()
+            .filter(|item| pockets.1.items.contains(item))
+            .collect()
+    }
+
+    fn shared_items_among(list: &[Rucksack]) -> Vec<Item> {
+        match list.split_first() {
+            Some((first, others)) => first
+                .unique_items()
+                .into_iter()
+                .filter(|item| others.iter().all(|x| x.items.contains(item)))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // Finds the minimum number of swaps between compartments so no item type ends up in both.
+    // Every item type is assigned to a single compartment, picking the assignment (via a
+    // subset-sum over item counts) that keeps the compartments' size with the fewest moves.
+    pub fn repair_plan(&self) -> Result<Vec<Swap>, RepairError> {
+        if !self.items.len().is_multiple_of(2) {
+            return Err(RepairError::OddLength(self.items.len()));
+        }
+
+        let half = self.items.len() / 2;
+        let (left, right) = self.compartments();
+        let counts: Vec<(Item, usize, usize)> = self
+            .unique_items()
+            .into_iter()
+            .map(|item| {
+                let count = |x: &Rucksack| x.items.iter().filter(|&&y| y == item).count();
+                (item, count(&left), count(&right))
+            })
+            .collect();
+
+        // best[i][size] is the minimum amount of items to move when the first `i` item types
+        // put `size` items into the left compartment
+        let mut best = vec![vec![None; half + 1]; counts.len() + 1];
+        best[0][0] = Some(0);
+        for (i, &(_, in_left, in_right)) in counts.iter().enumerate() {
+            for size in 0..=half {
+                let Some(moved) = best[i][size] else {
+                    continue;
+                };
+                let candidates = [
+                    (size + in_left + in_right, moved + in_right),
+                    (size, moved + in_left),
+                ];
+                for (next_size, next_moved) in candidates {
+                    if next_size <= half && best[i + 1][next_size].is_none_or(|x| next_moved < x) {
+                        best[i + 1][next_size] = Some(next_moved);
+                    }
+                }
+            }
+        }
+
+        let Some(mut moved) = best[counts.len()][half] else {
+            return Err(RepairError::Unbalanced);
+        };
+
+        let mut size = half;
+        let mut to_left = vec![];
+        for (i, &(item, in_left, in_right)) in counts.iter().enumerate().rev() {
+            let total = in_left + in_right;
+            let stays_left = size >= total
+                && moved >= in_right
+                && best[i][size - total] == Some(moved - in_right);
+            if stays_left {
+                to_left.push(item);
+                size -= total;
+                moved -= in_right;
+            } else {
+                moved -= in_left;
+            }
+        }
+
+        let outgoing = left.items.iter().positions(|x| !to_left.contains(x));
+        let incoming = right.items.iter().positions(|x| to_left.contains(x));
+
+        Ok(outgoing
+            .zip(incoming)
+            .map(|(left, right)| Swap { left, right })
+            .collect())
+    }
+
+    pub fn apply_swaps(&self, swaps: &[Swap]) -> Rucksack {
+        let half = self.items.len() / 2;
+        let mut items = self.items.clone();
+        for swap in swaps {
+            items.swap(swap.left, half + swap.right);
+        }
+        Rucksack { items }
+    }
+}
+
+// Positions are relative to each compartment
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Swap {
+    pub left: usize,
+    pub right: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RepairError {
+    OddLength(usize),
+    Unbalanced,
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OddLength(len) => write!(f, "odd number of items ({})", len),
+            Self::Unbalanced => {
+                write!(f, "item counts cannot be split evenly between compartments")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Violation {
+    OddLength {
+        line: usize,
+        len: usize,
+    },
+    InvalidItem {
+        line: usize,
+        column: usize,
+        item: Item,
+    },
+    NoSharedItem {
+        line: usize,
+    },
+    MultipleSharedItems {
+        line: usize,
+        items: Vec<Item>,
+    },
+    IncompleteGroup {
+        group: usize,
+        size: usize,
+        expected: usize,
+    },
+    NoBadge {
+        group: usize,
+    },
+    MultipleBadges {
+        group: usize,
+        items: Vec<Item>,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OddLength { line, len } => {
+                write!(f, "line {}: odd number of items ({})", line, len)
+            }
+            Self::InvalidItem { line, column, item } => {
+                write!(
+                    f,
+                    "line {}, column {}: invalid item {:?}",
+                    line, column, item
+                )
+            }
+            Self::NoSharedItem { line } => {
+                write!(f, "line {}: compartments share no items", line)
+            }
+            Self::MultipleSharedItems { line, items } => write!(
+                f,
+                "line {}: compartments share multiple items ({})",
+                line,
+                items.iter().collect::<String>()
+            ),
+            Self::IncompleteGroup {
+                group,
+                size,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "group {}: expected {} rucksacks, found {}",
+                    group, expected, size
+                )
+            }
+            Self::NoBadge { group } => write!(f, "group {}: no badge found", group),
+            Self::MultipleBadges { group, items } => write!(
+                f,
+                "group {}: multiple badge candidates ({})",
+                group,
+                items.iter().collect::<String>()
+            ),
+        }
+    }
+}
+
+fn is_valid_item(item: &Item) -> bool {
+    DefaultScheme.priority(*item).is_some()
+}
+
+// NOTE: line, column and group numbers are 1-based, so they can be matched against the puzzle
+// .     input in any text editor.
+pub fn validate_input(input: &str) -> Vec<Violation> {
+    validate_input_with_group_size(input, GROUP_SIZE).unwrap()
+}
+
+pub fn validate_input_with_group_size(
+    input: &str,
+    group_size: usize,
+) -> Result<Vec<Violation>, GroupingError> {
+    if group_size == 0 {
+        return Err(GroupingError::InvalidSize(group_size));
+    }
+
+    let mut violations = vec![];
+    let lines: Vec<&str> = input.lines().collect();
+
+    for (i, raw) in lines.iter().enumerate() {
+        let line = i + 1;
+        let rucksack = Rucksack::from(*raw);
+
+        let odd = !rucksack.items.len().is_multiple_of(2);
+        if odd {
+            violations.push(Violation::OddLength {
+                line,
+                len: rucksack.items.len(),
+            });
+        }
+
+        for (j, item) in rucksack.items.iter().enumerate() {
+            if !is_valid_item(item) {
+                violations.push(Violation::InvalidItem {
+                    line,
+                    column: j + 1,
+                    item: *item,
+                });
+            }
+        }
+
+        // compartments can't be told apart in an odd rucksack
+        if odd {
+            continue;
+        }
+        match rucksack.shared_items_in_compartments().as_slice() {
+            [] => violations.push(Violation::NoSharedItem { line }),
+            [_] => {}
+            items => violations.push(Violation::MultipleSharedItems {
+                line,
+                items: items.to_vec(),
+            }),
+        }
+    }
+
+    for (i, chunk) in lines.chunks(group_size).enumerate() {
+        let group = i + 1;
+        if chunk.len() < group_size {
+            violations.push(Violation::IncompleteGroup {
+                group,
+                size: chunk.len(),
+                expected: group_size,
+            });
+            continue;
+        }
+
+        let rucksacks: Vec<Rucksack> = chunk.iter().map(|x| Rucksack::from(*x)).collect();
+        match Rucksack::shared_items_among(&rucksacks).as_slice() {
+            [] => violations.push(Violation::NoBadge { group }),
+            [_] => {}
+            items => violations.push(Violation::MultipleBadges {
+                group,
+                items: items.to_vec(),
+            }),
+        }
+    }
+
+    Ok(violations)
 }
 
 type Group = Vec<Rucksack>;
 
+// The last group is left incomplete if the rucksacks can't be split evenly
+pub fn group_rucksacks(rucksacks: Vec<Rucksack>, size: usize) -> Result<Vec<Group>, GroupingError> {
+    if size == 0 {
+        return Err(GroupingError::InvalidSize(size));
+    }
+
+    Ok(rucksacks
+        .into_iter()
+        .chunks(size)
+        .into_iter()
+        .map(|x| x.collect())
+        .collect())
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum GroupingError {
+    InvalidSize(usize),
+    NotDivisible {
+        len: usize,
+        size: usize,
+    },
+    Impossible,
+    Ambiguous {
+        first: Vec<Vec<usize>>,
+        second: Vec<Vec<usize>>,
+    },
+}
+
+impl fmt::Display for GroupingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSize(size) => write!(f, "invalid group size: {}", size),
+            Self::NotDivisible { len, size } => write!(
+                f,
+                "{} rucksacks cannot be split into groups of {}",
+                len, size
+            ),
+            Self::Impossible => write!(f, "no partition with exactly one badge per group"),
+            Self::Ambiguous { first, second } => write!(
+                f,
+                "multiple partitions found, e.g. {:?} and {:?}",
+                first, second
+            ),
+        }
+    }
+}
+
+// NOTE: this is a backtracking search, so it's exponential in the worst case. Groups are built
+// .     around the first unassigned rucksack, and candidates are discarded as soon as the
+// .     running intersection of items is empty.
+pub fn discover_groups(rucksacks: &[Rucksack], size: usize) -> Result<Vec<Group>, GroupingError> {
+    if size == 0 {
+        return Err(GroupingError::InvalidSize(size));
+    }
+    if !rucksacks.len().is_multiple_of(size) {
+        return Err(GroupingError::NotDivisible {
+            len: rucksacks.len(),
+            size,
+        });
+    }
+
+    let mut search = GroupSearch {
+        rucksacks,
+        size,
+        assigned: vec![false; rucksacks.len()],
+        partition: vec![],
+        found: vec![],
+    };
+    search.run();
+
+    match search.found.len() {
+        0 => Err(GroupingError::Impossible),
+        1 => Ok(search.found[0]
+            .iter()
+            .map(|group| group.iter().map(|&i| rucksacks[i].clone()).collect())
+            .collect()),
+        _ => Err(GroupingError::Ambiguous {
+            first: search.found[0].clone(),
+            second: search.found[1].clone(),
+        }),
+    }
+}
+
+struct GroupSearch<'a> {
+    rucksacks: &'a [Rucksack],
+    size: usize,
+    assigned: Vec<bool>,
+    partition: Vec<Vec<usize>>,
+    found: Vec<Vec<Vec<usize>>>,
+}
+
+impl<'a> GroupSearch<'a> {
+    fn run(&mut self) {
+        if self.found.len() > 1 {
+            return;
+        }
+
+        let first = match self.assigned.iter().position(|&x| !x) {
+            Some(i) => i,
+            None => {
+                self.found.push(self.partition.clone());
+                return;
+            }
+        };
+
+        self.assigned[first] = true;
+        let items = self.rucksacks[first].unique_items();
+        self.pick_members(vec![first], items, first + 1);
+        self.assigned[first] = false;
+    }
+
+    fn pick_members(&mut self, group: Vec<usize>, items: Vec<Item>, from: usize) {
+        if group.len() == self.size {
+            if items.len() == 1 {
+                self.partition.push(group);
+                self.run();
+                self.partition.pop();
+            }
+            return;
+        }
+
+        for i in from..self.rucksacks.len() {
+            if self.assigned[i] || self.found.len() > 1 {
+                continue;
+            }
+
+            let shared: Vec<Item> = items
+                .iter()
+                .copied()
+                .filter(|item| self.rucksacks[i].items.contains(item))
+                .collect();
+            if shared.is_empty() {
+                continue;
+            }
+
+            self.assigned[i] = true;
+            let mut next = group.clone();
+            next.push(i);
+            self.pick_members(next, shared, i + 1);
+            self.assigned[i] = false;
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ItemFrequency {
+    pub item: Item,
+    pub rucksacks: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InventoryReport {
+    pub frequencies: Vec<ItemFrequency>,
+    pub misplaced: Vec<(Item, usize)>,
+    pub badges: Vec<(Item, usize)>,
+    pub most_shared_count: usize,
+    pub most_shared_pairs: Vec<(usize, usize)>,
+}
+
+fn count_by_item(items: impl Iterator<Item = Item>) -> Vec<(Item, usize)> {
+    items
+        .counts()
+        .into_iter()
+        .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+        .collect()
+}
+
+// NOTE: rucksacks in `most_shared_pairs` are referenced by their 0-based index in the input
+pub fn inventory_report(rucksacks: &[Rucksack], group_size: usize) -> InventoryReport {
+    let mut frequencies: HashMap<Item, ItemFrequency> = HashMap::new();
+    for rucksack in rucksacks {
+        let (left, right) = rucksack.compartments();
+        for item in rucksack.unique_items() {
+            let entry = frequencies.entry(item).or_insert(ItemFrequency {
+                item,
+                rucksacks: 0,
+                left: 0,
+                right: 0,
+            });
+            entry.rucksacks += 1;
+            entry.left += left.items.iter().filter(|&&x| x == item).count();
+            entry.right += right.items.iter().filter(|&&x| x == item).count();
+        }
+    }
+
+    let misplaced = count_by_item(
+        rucksacks
+            .iter()
+            .flat_map(|rucksack| rucksack.shared_items_in_compartments()),
+    );
+
+    let badges = count_by_item(
+        rucksacks
+            .chunks(group_size.max(1))
+            .filter(|group| group.len() == group_size)
+            .flat_map(Rucksack::shared_items_among),
+    );
+
+    let unique: Vec<Vec<Item>> = rucksacks.iter().map(Rucksack::unique_items).collect();
+    let mut most_shared_count = 0;
+    let mut most_shared_pairs = vec![];
+    for (i, j) in (0..rucksacks.len()).tuple_combinations() {
+        let count = count_shared_sorted(&unique[i], &unique[j]);
+        if count > most_shared_count {
+            most_shared_count = count;
+            most_shared_pairs.clear();
+        }
+        if count == most_shared_count && count > 0 {
+            most_shared_pairs.push((i, j));
+        }
+    }
+
+    InventoryReport {
+        frequencies: frequencies
+            .into_values()
+            .sorted_by_key(|x| x.item)
+            .collect(),
+        misplaced,
+        badges,
+        most_shared_count,
+        most_shared_pairs,
+    }
+}
+
+// Items in both lists, which must be sorted and without duplicates
+fn count_shared_sorted(a: &[Item], b: &[Item]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+fn json_string(item: Item) -> String {
+    match item {
+        '"' => "\\\"".to_string(),
+        '\\' => "\\\\".to_string(),
+        x if x.is_control() => format!("\\u{:04x}", x as u32),
+        x => x.to_string(),
+    }
+}
+
+impl InventoryReport {
+    pub fn to_table(&self) -> String {
+        let mut out = String::from("item  rucksacks  left  right\n");
+        for x in &self.frequencies {
+            out += &format!(
+                "{:<4}  {:>9}  {:>4}  {:>5}\n",
+                x.item, x.rucksacks, x.left, x.right
+            );
+        }
+
+        out += "\nmisplaced  count\n";
+        for (item, count) in &self.misplaced {
+            out += &format!("{:<9}  {:>5}\n", item, count);
+        }
+
+        out += "\nbadge  groups\n";
+        for (item, count) in &self.badges {
+            out += &format!("{:<5}  {:>6}\n", item, count);
+        }
+
+        out += &format!(
+            "\nmost shared items between two rucksacks: {}\n",
+            self.most_shared_count
+        );
+        for (i, j) in &self.most_shared_pairs {
+            out += &format!("{} {}\n", i, j);
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let frequencies = self
+            .frequencies
+            .iter()
+            .map(|x| {
+                format!(
+                    r#"{{"item":"{}","rucksacks":{},"left":{},"right":{}}}"#,
+                    json_string(x.item),
+                    x.rucksacks,
+                    x.left,
+                    x.right
+                )
+            })
+            .join(",");
+        let counts = |list: &[(Item, usize)]| {
+            list.iter()
+                .map(|(item, count)| {
+                    format!(r#"{{"item":"{}","count":{}}}"#, json_string(*item), count)
+                })
+                .join(",")
+        };
+        let pairs = self
+            .most_shared_pairs
+            .iter()
+            .map(|(i, j)| format!("[{},{}]", i, j))
+            .join(",");
+
+        format!(
+            r#"{{"frequencies":[{}],"misplaced":[{}],"badges":[{}],"most_shared_count":{},"most_shared_pairs":[{}]}}"#,
+            frequencies,
+            counts(&self.misplaced),
+            counts(&self.badges),
+            self.most_shared_count,
+            pairs
+        )
+    }
+}
+
 #[aoc_generator(day3, part1)]
 pub fn parse_input_part1(input: &str) -> Vec<Rucksack> {
-    input.lines().map(|x| Rucksack::from(x)).collect()
+    input.lines().map(Rucksack::from).collect()
 }
 
 #[aoc_generator(day3, part2)]
 pub fn parse_input_part2(input: &str) -> Vec<Group> {
-    let rucksacks = parse_input_part1(input);
-    rucksacks
-        .into_iter()
-        .chunks(3)
-        .into_iter()
-        .map(|x| x.collect())
-        .collect()
+    group_rucksacks(parse_input_part1(input), GROUP_SIZE).unwrap()
 }
 
-#[aoc(day3, part1)]
-pub fn solve_part1(input: &[Rucksack]) -> u32 {
+// Returns `None` if any of the items to prioritize is not supported by the scheme
+pub fn solve_part1_with_scheme<S: PriorityScheme>(input: &[Rucksack], scheme: &S) -> Option<u32> {
     input
-        .into_iter()
-        .map(|rucksack| rucksack.common_item_in_compartments().priority())
+        .iter()
+        .map(|rucksack| scheme.priority(rucksack.common_item_in_compartments()))
         .sum()
 }
 
-#[aoc(day3, part2)]
-pub fn solve_part2(input: &[Group]) -> u32 {
+pub fn solve_part2_with_scheme<S: PriorityScheme>(input: &[Group], scheme: &S) -> Option<u32> {
     input
-        .into_iter()
-        .map(|group| Rucksack::common_item_among(&group).priority())
+        .iter()
+        .map(|group| scheme.priority(Rucksack::common_item_among(group)))
         .sum()
 }
 
+#[aoc(day3, part1)]
+pub fn solve_part1(input: &[Rucksack]) -> u32 {
+    solve_part1_with_scheme(input, &DefaultScheme).expect("item without priority")
+}
+
+#[aoc(day3, part2)]
+pub fn solve_part2(input: &[Group]) -> u32 {
+    solve_part2_with_scheme(input, &DefaultScheme).expect("item without priority")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,7 +903,7 @@ wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw"#;
         assert_eq!(
-            parse_input_part2(&input),
+            parse_input_part2(input),
             vec![
                 [
                     Rucksack::from("vJrwpWtwJgWrhcsFMMfFFhFp"),
@@ -211,4 +936,345 @@ CrZsJsPPZsGzwwsLwLmpwMDw"#;
 
         assert_eq!(solve_part2(&input), 70)
     }
+
+    #[test]
+    fn test_validate_input_with_valid_input() {
+        let input = r#"vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg"#;
+        assert_eq!(validate_input(input), vec![]);
+    }
+
+    #[test]
+    fn test_validate_input_with_odd_rucksack() {
+        assert_eq!(
+            validate_input("abc"),
+            vec![
+                Violation::OddLength { line: 1, len: 3 },
+                Violation::IncompleteGroup {
+                    group: 1,
+                    size: 1,
+                    expected: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_input_with_invalid_rucksacks() {
+        let input = r#"abcaa
+abAB
+abab
+a1a1
+abcd"#;
+        assert_eq!(
+            validate_input(input),
+            vec![
+                Violation::OddLength { line: 1, len: 5 },
+                Violation::NoSharedItem { line: 2 },
+                Violation::MultipleSharedItems {
+                    line: 3,
+                    items: vec!['a', 'b']
+                },
+                Violation::InvalidItem {
+                    line: 4,
+                    column: 2,
+                    item: '1'
+                },
+                Violation::InvalidItem {
+                    line: 4,
+                    column: 4,
+                    item: '1'
+                },
+                Violation::MultipleSharedItems {
+                    line: 4,
+                    items: vec!['1', 'a']
+                },
+                Violation::NoSharedItem { line: 5 },
+                Violation::MultipleBadges {
+                    group: 1,
+                    items: vec!['a', 'b']
+                },
+                Violation::IncompleteGroup {
+                    group: 2,
+                    size: 2,
+                    expected: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_input_with_no_badge() {
+        let input = r#"aa
+bb
+cc"#;
+        assert_eq!(validate_input(input), vec![Violation::NoBadge { group: 1 }]);
+    }
+
+    #[test]
+    fn test_validate_input_with_group_size() {
+        let input = r#"vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg"#;
+        assert_eq!(
+            validate_input_with_group_size(input, 2),
+            Ok(vec![
+                Violation::MultipleBadges {
+                    group: 1,
+                    items: vec!['F', 'M', 'f', 'r', 's']
+                },
+                Violation::IncompleteGroup {
+                    group: 2,
+                    size: 1,
+                    expected: 2
+                },
+            ])
+        );
+        assert_eq!(
+            validate_input_with_group_size(input, 0),
+            Err(GroupingError::InvalidSize(0))
+        );
+    }
+
+    #[test]
+    fn test_group_rucksacks() {
+        let rucksacks = vec![
+            Rucksack::from("aa"),
+            Rucksack::from("ab"),
+            Rucksack::from("bb"),
+            Rucksack::from("cc"),
+        ];
+        assert_eq!(
+            group_rucksacks(rucksacks.clone(), 2),
+            Ok(vec![
+                vec![Rucksack::from("aa"), Rucksack::from("ab")],
+                vec![Rucksack::from("bb"), Rucksack::from("cc")],
+            ])
+        );
+        assert_eq!(
+            group_rucksacks(rucksacks, 0),
+            Err(GroupingError::InvalidSize(0))
+        );
+    }
+
+    #[test]
+    fn test_discover_groups() {
+        let rucksacks = vec![
+            Rucksack::from("axay"),
+            Rucksack::from("bzbw"),
+            Rucksack::from("aqar"),
+            Rucksack::from("bsbt"),
+        ];
+        assert_eq!(
+            discover_groups(&rucksacks, 2),
+            Ok(vec![
+                vec![Rucksack::from("axay"), Rucksack::from("aqar")],
+                vec![Rucksack::from("bzbw"), Rucksack::from("bsbt")],
+            ])
+        );
+    }
+
+    #[test]
+    fn test_discover_groups_errors() {
+        let rucksacks = vec![
+            Rucksack::from("aa"),
+            Rucksack::from("aa"),
+            Rucksack::from("aa"),
+            Rucksack::from("aa"),
+        ];
+        assert_eq!(
+            discover_groups(&rucksacks, 0),
+            Err(GroupingError::InvalidSize(0))
+        );
+        assert_eq!(
+            discover_groups(&rucksacks, 3),
+            Err(GroupingError::NotDivisible { len: 4, size: 3 })
+        );
+        assert_eq!(
+            discover_groups(&rucksacks, 2),
+            Err(GroupingError::Ambiguous {
+                first: vec![vec![0, 1], vec![2, 3]],
+                second: vec![vec![0, 2], vec![1, 3]],
+            })
+        );
+        assert_eq!(
+            discover_groups(&[Rucksack::from("aa"), Rucksack::from("bb")], 2),
+            Err(GroupingError::Impossible)
+        );
+    }
+
+    #[test]
+    fn test_default_scheme() {
+        assert_eq!(DefaultScheme.priority('a'), Some(1));
+        assert_eq!(DefaultScheme.priority('z'), Some(26));
+        assert_eq!(DefaultScheme.priority('A'), Some(27));
+        assert_eq!(DefaultScheme.priority('Z'), Some(52));
+        assert_eq!(DefaultScheme.priority('ñ'), None);
+    }
+
+    #[test]
+    fn test_unicode_scheme() {
+        assert_eq!(UnicodeScheme.priority('p'), Some(16));
+        assert_eq!(UnicodeScheme.priority('P'), Some(42));
+        assert_eq!(UnicodeScheme.priority('ñ'), Some(52 + 0xf1));
+        assert_eq!(UnicodeScheme.priority(' '), None);
+    }
+
+    #[test]
+    fn test_table_scheme_from_str() {
+        let scheme = TableScheme::from_str("a 10\n\nñ 2\n").unwrap();
+        assert_eq!(scheme.priority('a'), Some(10));
+        assert_eq!(scheme.priority('ñ'), Some(2));
+        assert_eq!(scheme.priority('b'), None);
+
+        assert_eq!(
+            TableScheme::from_str("a 1\nb"),
+            Err(TableError::MissingPriority { line: 2 })
+        );
+        assert_eq!(
+            TableScheme::from_str("ab 1"),
+            Err(TableError::InvalidItem {
+                line: 1,
+                token: "ab".to_string()
+            })
+        );
+        assert_eq!(
+            TableScheme::from_str("a x"),
+            Err(TableError::InvalidPriority {
+                line: 1,
+                token: "x".to_string()
+            })
+        );
+        assert_eq!(
+            TableScheme::from_str("a 1\na 2"),
+            Err(TableError::DuplicateItem { line: 2, item: 'a' })
+        );
+    }
+
+    #[test]
+    fn test_solve_with_scheme() {
+        let rucksacks = vec![Rucksack::from("abñxyñ"), Rucksack::from("ñcñd")];
+        let scheme = TableScheme::from_order("abñ");
+
+        assert_eq!(solve_part1_with_scheme(&rucksacks, &scheme), Some(6));
+        assert_eq!(solve_part1_with_scheme(&rucksacks, &DefaultScheme), None);
+        assert_eq!(
+            solve_part2_with_scheme(&[rucksacks], &UnicodeScheme),
+            Some(52 + 0xf1)
+        );
+    }
+
+    #[test]
+    fn test_inventory_report() {
+        let rucksacks = vec![
+            Rucksack::from("abca"),
+            Rucksack::from("aaxb"),
+            Rucksack::from("dbdb"),
+        ];
+        let report = inventory_report(&rucksacks, 3);
+
+        assert_eq!(
+            report.frequencies,
+            vec![
+                ItemFrequency {
+                    item: 'a',
+                    rucksacks: 2,
+                    left: 3,
+                    right: 1
+                },
+                ItemFrequency {
+                    item: 'b',
+                    rucksacks: 3,
+                    left: 2,
+                    right: 2
+                },
+                ItemFrequency {
+                    item: 'c',
+                    rucksacks: 1,
+                    left: 0,
+                    right: 1
+                },
+                ItemFrequency {
+                    item: 'd',
+                    rucksacks: 1,
+                    left: 1,
+                    right: 1
+                },
+                ItemFrequency {
+                    item: 'x',
+                    rucksacks: 1,
+                    left: 0,
+                    right: 1
+                },
+            ]
+        );
+        assert_eq!(report.misplaced, vec![('a', 1), ('b', 1), ('d', 1)]);
+        assert_eq!(report.badges, vec![('b', 1)]);
+        assert_eq!(report.most_shared_count, 2);
+        assert_eq!(report.most_shared_pairs, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_inventory_report_to_json() {
+        let report = inventory_report(&[Rucksack::from("a\"a\"")], 1);
+        assert_eq!(
+            report.to_json(),
+            r#"{"frequencies":[{"item":"\"","rucksacks":1,"left":1,"right":1},{"item":"a","rucksacks":1,"left":1,"right":1}],"misplaced":[{"item":"\"","count":1},{"item":"a","count":1}],"badges":[{"item":"\"","count":1},{"item":"a","count":1}],"most_shared_count":0,"most_shared_pairs":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_inventory_report_to_table() {
+        let report = inventory_report(&[Rucksack::from("aa"), Rucksack::from("ab")], 2);
+        assert_eq!(
+            report.to_table(),
+            r#"item  rucksacks  left  right
+a             2     2      1
+b             1     0      1
+
+misplaced  count
+a              1
+
+badge  groups
+a           1
+
+most shared items between two rucksacks: 1
+0 1
+"#
+        );
+    }
+
+    #[test]
+    fn test_rucksack_repair_plan() {
+        let rucksack = Rucksack::from("vJrwpWtwJgWrhcsFMMfFFhFp");
+        let plan = rucksack.repair_plan().unwrap();
+        assert_eq!(plan, vec![Swap { left: 4, right: 2 }]);
+        assert_eq!(
+            rucksack.apply_swaps(&plan).shared_items_in_compartments(),
+            vec![]
+        );
+
+        let rucksack = Rucksack::from("aabbab");
+        let plan = rucksack.repair_plan().unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(
+            rucksack.apply_swaps(&plan).shared_items_in_compartments(),
+            vec![]
+        );
+
+        assert_eq!(Rucksack::from("abcd").repair_plan(), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_rucksack_repair_plan_errors() {
+        assert_eq!(
+            Rucksack::from("abc").repair_plan(),
+            Err(RepairError::OddLength(3))
+        );
+        assert_eq!(
+            Rucksack::from("aaab").repair_plan(),
+            Err(RepairError::Unbalanced)
+        );
+    }
 }
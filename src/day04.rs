@@ -1,55 +1,726 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
+use itertools::Itertools;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::iter::FromIterator;
 
 type Section = std::ops::RangeInclusive<usize>;
-type Pair = (Section, Section);
+pub type Group = Vec<Section>;
 
-trait Assignment {
+// A set of sections stored as sorted, disjoint and non-adjacent inclusive ranges
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct IntervalSet {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = Section> + '_ {
+        self.ranges.iter().map(|&(start, end)| start..=end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    // Number of sections in the set
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|(start, end)| end - start + 1).sum()
+    }
+
+    pub fn insert(&mut self, section: Section) {
+        if section.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = (*section.start(), *section.end());
+
+        // ranges that overlap or touch the new one get merged into it
+        let first = self
+            .ranges
+            .partition_point(|&(_, x)| x.saturating_add(1) < start);
+        let last = self
+            .ranges
+            .partition_point(|&(x, _)| x <= end.saturating_add(1));
+        if first < last {
+            start = start.min(self.ranges[first].0);
+            end = end.max(self.ranges[last - 1].1);
+        }
+        self.ranges.splice(first..last, [(start, end)]);
+    }
+
+    pub fn contains(&self, section: usize) -> bool {
+        let i = self.ranges.partition_point(|&(_, end)| end < section);
+        self.ranges
+            .get(i)
+            .is_some_and(|&(start, _)| start <= section)
+    }
+
+    pub fn contains_set(&self, other: &IntervalSet) -> bool {
+        other.ranges.iter().all(|&(start, end)| {
+            let i = self.ranges.partition_point(|&(_, x)| x < start);
+            self.ranges
+                .get(i)
+                .is_some_and(|&(x, y)| x <= start && end <= y)
+        })
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut res = self.clone();
+        for section in other.ranges() {
+            res.insert(section);
+        }
+        res
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            let (start, end) = (a.0.max(b.0), a.1.min(b.1));
+            if start <= end {
+                ranges.push((start, end));
+            }
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = vec![];
+        for &(start, end) in &self.ranges {
+            let first = other.ranges.partition_point(|&(_, y)| y < start);
+            let mut current = Some(start);
+            for &(x, y) in other.ranges[first..].iter().take_while(|(x, _)| *x <= end) {
+                let Some(from) = current else {
+                    break;
+                };
+                if x > from {
+                    ranges.push((from, x - 1));
+                }
+                current = y.checked_add(1).filter(|&next| next <= end);
+            }
+            if let Some(from) = current {
+                ranges.push((from, end));
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+impl From<Section> for IntervalSet {
+    fn from(section: Section) -> Self {
+        let mut res = Self::new();
+        res.insert(section);
+        res
+    }
+}
+
+impl FromIterator<Section> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Section>>(iter: I) -> Self {
+        let mut res = Self::new();
+        for section in iter {
+            res.insert(section);
+        }
+        res
+    }
+}
+
+pub trait Assignment {
     fn is_redundant(&self) -> bool;
     fn has_overlap(&self) -> bool;
+    fn has_common_section(&self) -> bool;
+    fn overlapping_pairs(&self) -> usize;
 }
 
-impl Assignment for Pair {
+impl Assignment for [Section] {
+    // Whether any section range is fully contained in another one
     fn is_redundant(&self) -> bool {
-        (self.0.start() >= self.1.start() && self.0.end() <= self.1.end())
-            || (self.1.start() >= self.0.start() && self.1.end() <= self.0.end())
+        let sets: Vec<IntervalSet> = self.iter().cloned().map(IntervalSet::from).collect();
+        sets.iter().enumerate().any(|(i, a)| {
+            sets.iter()
+                .enumerate()
+                .any(|(j, b)| i != j && b.contains_set(a))
+        })
     }
 
+    // Whether any two section ranges share at least one section
     fn has_overlap(&self) -> bool {
-        (self.0.contains(self.1.start()) || self.0.contains(self.1.end()))
-            || (self.1.contains(self.0.start()) || self.1.contains(self.0.end()))
+        self.overlapping_pairs() > 0
+    }
+
+    // Whether there's at least one section assigned to every elf
+    fn has_common_section(&self) -> bool {
+        match self.split_first() {
+            Some((first, others)) => !others
+                .iter()
+                .fold(IntervalSet::from(first.clone()), |res, x| {
+                    res.intersection(&IntervalSet::from(x.clone()))
+                })
+                .is_empty(),
+            None => false,
+        }
+    }
+
+    fn overlapping_pairs(&self) -> usize {
+        let sets: Vec<IntervalSet> = self.iter().cloned().map(IntervalSet::from).collect();
+        sets.iter()
+            .enumerate()
+            .map(|(i, a)| {
+                sets[i + 1..]
+                    .iter()
+                    .filter(|b| !a.intersection(b).is_empty())
+                    .count()
+            })
+            .sum()
     }
 }
 
-#[aoc_generator(day4)]
-pub fn parse_input(input: &str) -> Vec<Pair> {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GroupReport {
+    pub line: usize,
+    pub size: usize,
+    pub is_redundant: bool,
+    pub has_common_section: bool,
+    pub overlapping_pairs: usize,
+}
+
+impl fmt::Display for GroupReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} elves, redundant: {}, common section: {}, overlapping pairs: {}",
+            self.line,
+            self.size,
+            self.is_redundant,
+            self.has_common_section,
+            self.overlapping_pairs
+        )
+    }
+}
+
+// NOTE: line numbers are 1-based
+pub fn report(input: &[Group]) -> Vec<GroupReport> {
     input
-        .lines()
-        .into_iter()
-        .map(|x| {
-            let pairs: Vec<Section> = x
-                .split(",")
-                .map(|pair| {
-                    let mut tokens = pair.split("-");
-                    let start = tokens.next().unwrap().parse::<usize>().unwrap();
-                    let end = tokens.next().unwrap().parse::<usize>().unwrap();
-
-                    start..=end
-                })
+        .iter()
+        .enumerate()
+        .map(|(i, group)| GroupReport {
+            line: i + 1,
+            size: group.len(),
+            is_redundant: group.is_redundant(),
+            has_common_section: group.has_common_section(),
+            overlapping_pairs: group.overlapping_pairs(),
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Coverage {
+    // sections between the lowest and highest assigned ones that nobody cleans
+    pub uncovered: IntervalSet,
+    // sections cleaned by more than the given amount of elves
+    pub over_covered: IntervalSet,
+    pub max_depth: usize,
+    pub max_depth_at: IntervalSet,
+    // sections cleaned more than once, counting every extra time
+    pub wasted: usize,
+}
+
+// Sweeps through the start and end of every section range, so it runs in O(n log n)
+pub fn coverage(input: &[Group], k: usize) -> Coverage {
+    let mut events: Vec<(usize, isize)> = input
+        .iter()
+        .flatten()
+        .filter(|section| !section.is_empty())
+        .flat_map(|section| {
+            let start = Some((*section.start(), 1));
+            let end = section.end().checked_add(1).map(|x| (x, -1));
+            [start, end].into_iter().flatten()
+        })
+        .collect();
+    events.sort_unstable();
+
+    let mut res = Coverage::default();
+    let mut depth: isize = 0;
+    let mut i = 0;
+    while i < events.len() {
+        let position = events[i].0;
+        while i < events.len() && events[i].0 == position {
+            depth += events[i].1;
+            i += 1;
+        }
+
+        let end = match events.get(i) {
+            Some(&(next, _)) => next - 1,
+            None if depth > 0 => usize::MAX,
+            None => break,
+        };
+        let segment = position..=end;
+        let len = end - position + 1;
+        let current = depth as usize;
+
+        if current == 0 {
+            res.uncovered.insert(segment.clone());
+        }
+        if current > k {
+            res.over_covered.insert(segment.clone());
+        }
+        if current > 1 {
+            res.wasted += (current - 1) * len;
+        }
+        if current > res.max_depth {
+            res.max_depth = current;
+            res.max_depth_at = IntervalSet::new();
+        }
+        if current == res.max_depth && current > 0 {
+            res.max_depth_at.insert(segment);
+        }
+    }
+
+    res
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReassignMode {
+    // elves in the same line must not overlap
+    PerGroup,
+    // no two elves in the whole camp may overlap
+    Global,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reassignment {
+    pub groups: Vec<Group>,
+    pub movement: usize,
+    // `false` when `movement` is only minimal for the midpoint order of the ranges
+    pub optimal: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ReassignError {
+    // `line` is 1-based, and `None` in global mode
+    Infeasible {
+        line: Option<usize>,
+        needed: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for ReassignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Infeasible {
+                line,
+                needed,
+                available,
+            } => {
+                if let Some(line) = line {
+                    write!(f, "line {}: ", line)?;
+                }
+                write!(
+                    f,
+                    "{} sections needed, but only {} available",
+                    needed, available
+                )
+            }
+        }
+    }
+}
+
+// Shifts section ranges within `bounds` so they don't overlap, keeping their length and
+// minimizing the total movement. Every order of the ranges is tried for small sets; for larger
+// ones ranges keep the order of their midpoints and movement is minimal for that order.
+// NOTE: the midpoint order is exact when all ranges have the same length, otherwise the result
+// .     is a heuristic and `Reassignment::optimal` is `false`
+pub fn reassign(
+    input: &[Group],
+    bounds: Section,
+    mode: ReassignMode,
+) -> Result<Reassignment, ReassignError> {
+    let available = if bounds.is_empty() {
+        0
+    } else {
+        bounds.end() - bounds.start() + 1
+    };
+
+    let mut groups = input.to_vec();
+    let mut movement = 0;
+    let mut optimal = true;
+    match mode {
+        ReassignMode::PerGroup => {
+            for (i, group) in input.iter().enumerate() {
+                let (placed, cost, exact) =
+                    pack(group, &bounds).ok_or_else(|| ReassignError::Infeasible {
+                        line: Some(i + 1),
+                        needed: total_length(group),
+                        available,
+                    })?;
+                groups[i] = placed;
+                movement += cost;
+                optimal &= exact;
+            }
+        }
+        ReassignMode::Global => {
+            let all: Vec<Section> = input.iter().flatten().cloned().collect();
+            let (placed, cost, exact) =
+                pack(&all, &bounds).ok_or_else(|| ReassignError::Infeasible {
+                    line: None,
+                    needed: total_length(&all),
+                    available,
+                })?;
+            let mut placed = placed.into_iter();
+            for group in groups.iter_mut() {
+                for section in group.iter_mut() {
+                    *section = placed.next().unwrap();
+                }
+            }
+            movement = cost;
+            optimal = exact;
+        }
+    }
+
+    Ok(Reassignment {
+        groups,
+        movement,
+        optimal,
+    })
+}
+
+const MAX_PERMUTED_SECTIONS: usize = 6;
+
+fn total_length(sections: &[Section]) -> usize {
+    sections.iter().map(|x| x.clone().count()).sum()
+}
+
+// Also returns whether the placement is known to be movement-minimal
+fn pack(sections: &[Section], bounds: &Section) -> Option<(Vec<Section>, usize, bool)> {
+    let ranges: Vec<usize> = (0..sections.len())
+        .filter(|&i| !sections[i].is_empty())
+        .collect();
+
+    // two equal-length ranges placed out of start order can always be swapped without adding
+    // movement, so sorting them is exact
+    let exact = ranges.len() <= MAX_PERMUTED_SECTIONS
+        || ranges
+            .iter()
+            .map(|&i| sections[i].end() - sections[i].start())
+            .all_equal();
+
+    let best = if ranges.len() <= MAX_PERMUTED_SECTIONS {
+        ranges
+            .iter()
+            .copied()
+            .permutations(ranges.len())
+            .filter_map(|order| place_in_order(sections, &order, bounds))
+            .min_by_key(|(_, cost)| *cost)
+    } else {
+        let order: Vec<usize> = ranges
+            .into_iter()
+            .sorted_by_key(|&i| sections[i].start() + sections[i].end())
+            .collect();
+        place_in_order(sections, &order, bounds)
+    };
+
+    best.map(|(starts, cost)| {
+        let placed = sections
+            .iter()
+            .zip(starts)
+            .map(|(section, start)| match start {
+                Some(x) => x..=(x + section.end() - section.start()),
+                None => section.clone(),
+            })
+            .collect();
+        (placed, cost, exact)
+    })
+}
+
+// Places ranges one after another in the given order. With `z[i] = start[i] - (lengths of the
+// ranges before i)` the ranges don't overlap as long as `z` is non-decreasing, so this becomes an
+// L1 isotonic regression, solved with a max-heap ("slope trick") and then clamped to `bounds`.
+fn place_in_order(
+    sections: &[Section],
+    order: &[usize],
+    bounds: &Section,
+) -> Option<(Vec<Option<usize>>, usize)> {
+    let mut starts = vec![None; sections.len()];
+    if order.is_empty() {
+        return Some((starts, 0));
+    }
+
+    let lengths: Vec<i128> = order
+        .iter()
+        .map(|&i| (sections[i].end() - sections[i].start() + 1) as i128)
+        .collect();
+    let offsets: Vec<i128> = lengths
+        .iter()
+        .scan(0, |acc, len| {
+            let offset = *acc;
+            *acc += len;
+            Some(offset)
+        })
+        .collect();
+    let total: i128 = lengths.iter().sum();
+
+    let low = *bounds.start() as i128;
+    let high = *bounds.end() as i128 - total + 1;
+    if bounds.is_empty() || high < low {
+        return None;
+    }
+
+    let targets: Vec<i128> = order
+        .iter()
+        .zip(&offsets)
+        .map(|(&i, offset)| *sections[i].start() as i128 - offset)
+        .collect();
+
+    let mut heap = BinaryHeap::new();
+    let mut fitted = Vec::with_capacity(targets.len());
+    for &target in &targets {
+        heap.push(target);
+        if *heap.peek().unwrap() > target {
+            heap.pop();
+            heap.push(target);
+        }
+        fitted.push(*heap.peek().unwrap());
+    }
+    for i in (0..fitted.len() - 1).rev() {
+        fitted[i] = fitted[i].min(fitted[i + 1]);
+    }
+
+    let mut cost = 0;
+    for (k, &i) in order.iter().enumerate() {
+        let z = fitted[k].clamp(low, high);
+        cost += (z - targets[k]).unsigned_abs() as usize;
+        starts[i] = Some((z + offsets[k]) as usize);
+    }
+
+    Some((starts, cost))
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IndexedSection {
+    // 1-based line of the input and 1-based position of the elf within it
+    pub line: usize,
+    pub elf: usize,
+    pub section: Section,
+}
+
+// Static interval index: sections are sorted by start, so the ones overlapping `a..=b` are those
+// in the prefix starting at most at `b` that end at least at `a`. A sparse table keeps, for every
+// power-of-two window, the position of the section with the highest end, and every lookup in it
+// either reports a section or discards a whole window. Queries run in O(log n + k), after an
+// O(n log n) build.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SectionIndex {
+    entries: Vec<IndexedSection>,
+    // `highest_end[j][i]` is the position of the highest end among `entries[i..i + 2^j]`
+    highest_end: Vec<Vec<usize>>,
+}
+
+enum IndexStep {
+    Window(usize, usize),
+    Report(usize),
+}
+
+impl SectionIndex {
+    pub fn new(input: &[Group]) -> Self {
+        let mut entries: Vec<IndexedSection> = input
+            .iter()
+            .enumerate()
+            .flat_map(|(i, group)| {
+                group
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, section)| IndexedSection {
+                        line: i + 1,
+                        elf: j + 1,
+                        section: section.clone(),
+                    })
+            })
+            .filter(|x| !x.section.is_empty())
+            .collect();
+        entries.sort_by_key(|x| (*x.section.start(), *x.section.end(), x.line, x.elf));
+
+        let mut index = Self {
+            highest_end: vec![(0..entries.len()).collect()],
+            entries,
+        };
+        let mut width = 1;
+        while width * 2 <= index.entries.len() {
+            let prev = index.highest_end.last().unwrap();
+            let level = (0..=index.entries.len() - width * 2)
+                .map(|i| index.highest(prev[i], prev[i + width]))
                 .collect();
-            (pairs[0].clone(), pairs[1].clone())
+            index.highest_end.push(level);
+            width *= 2;
+        }
+        index
+    }
+
+    fn highest(&self, a: usize, b: usize) -> usize {
+        if self.entries[b].section.end() > self.entries[a].section.end() {
+            b
+        } else {
+            a
+        }
+    }
+
+    // Position of the highest end among `entries[lo..hi]`, which must not be empty
+    fn highest_in(&self, lo: usize, hi: usize) -> usize {
+        let level = (hi - lo).ilog2() as usize;
+        let table = &self.highest_end[level];
+        self.highest(table[lo], table[hi - (1 << level)])
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Assignments sharing at least one section with `range`, sorted by their start
+    pub fn overlapping(&self, range: Section) -> Vec<&IndexedSection> {
+        let mut res = vec![];
+        if range.is_empty() {
+            return res;
+        }
+
+        let prefix = self
+            .entries
+            .partition_point(|x| x.section.start() <= range.end());
+        // windows are walked in order, so results come out sorted like `entries`
+        let mut steps = vec![IndexStep::Window(0, prefix)];
+        while let Some(step) = steps.pop() {
+            match step {
+                IndexStep::Report(i) => res.push(&self.entries[i]),
+                IndexStep::Window(lo, hi) => {
+                    if lo >= hi {
+                        continue;
+                    }
+                    let i = self.highest_in(lo, hi);
+                    if self.entries[i].section.end() < range.start() {
+                        continue;
+                    }
+                    steps.push(IndexStep::Window(i + 1, hi));
+                    steps.push(IndexStep::Report(i));
+                    steps.push(IndexStep::Window(lo, i));
+                }
+            }
+        }
+        res
+    }
+
+    pub fn containing(&self, section: usize) -> Vec<&IndexedSection> {
+        self.overlapping(section..=section)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ReversedRanges {
+    #[default]
+    Reject,
+    // `8-2` is read as `2-8`
+    Normalize,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ParseOptions {
+    pub reversed_ranges: ReversedRanges,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseErrorKind {
+    MissingDash,
+    InvalidNumber,
+    ReversedRange,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    // 1-based
+    pub line: usize,
+    pub token: String,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            ParseErrorKind::MissingDash => "expected a range like `2-4`",
+            ParseErrorKind::InvalidNumber => "invalid section number",
+            ParseErrorKind::ReversedRange => "range start is greater than its end",
+        };
+        write!(f, "line {}: {} in {:?}", self.line, reason, self.token)
+    }
+}
+
+// `line` is 1-based, and only used to report errors
+pub fn parse_section_range(
+    raw: &str,
+    line: usize,
+    options: &ParseOptions,
+) -> Result<Section, ParseError> {
+    let token = raw.trim();
+    let error = |kind| ParseError {
+        line,
+        token: token.to_string(),
+        kind,
+    };
+
+    let (start, end) = token
+        .split_once('-')
+        .ok_or_else(|| error(ParseErrorKind::MissingDash))?;
+    let start = start
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| error(ParseErrorKind::InvalidNumber))?;
+    let end = end
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| error(ParseErrorKind::InvalidNumber))?;
+
+    match (start <= end, options.reversed_ranges) {
+        (true, _) => Ok(start..=end),
+        (false, ReversedRanges::Normalize) => Ok(end..=start),
+        (false, ReversedRanges::Reject) => Err(error(ParseErrorKind::ReversedRange)),
+    }
+}
+
+pub fn parse_input_with(input: &str, options: &ParseOptions) -> Result<Vec<Group>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, x)| {
+            x.split(',')
+                .map(|pair| parse_section_range(pair, i + 1, options))
+                .collect()
         })
         .collect()
 }
 
+#[aoc_generator(day4)]
+pub fn parse_input(input: &str) -> Vec<Group> {
+    parse_input_with(input, &ParseOptions::default()).unwrap_or_else(|err| panic!("{}", err))
+}
+
 #[aoc(day4, part1)]
-pub fn solve_part1(input: &[Pair]) -> u64 {
-    input.into_iter().filter(|x| x.is_redundant()).count() as u64
+pub fn solve_part1(input: &[Group]) -> u64 {
+    input.iter().filter(|x| x.is_redundant()).count() as u64
 }
 
 #[aoc(day4, part2)]
-pub fn solve_part2(input: &[Pair]) -> u64 {
-    input.into_iter().filter(|x| x.has_overlap()).count() as u64
+pub fn solve_part2(input: &[Group]) -> u64 {
+    input.iter().filter(|x| x.has_overlap()).count() as u64
 }
 
 #[cfg(test)]
@@ -67,12 +738,12 @@ mod tests {
         assert_eq!(
             parse_input(input),
             vec![
-                (2..=4, 6..=8),
-                (2..=3, 4..=5),
-                (5..=7, 7..=9),
-                (2..=8, 3..=7),
-                (6..=6, 4..=6),
-                (2..=6, 4..=8),
+                vec![2..=4, 6..=8],
+                vec![2..=3, 4..=5],
+                vec![5..=7, 7..=9],
+                vec![2..=8, 3..=7],
+                vec![6..=6, 4..=6],
+                vec![2..=6, 4..=8],
             ]
         )
     }
@@ -80,12 +751,12 @@ mod tests {
     #[test]
     pub fn test_solve_part1() {
         let input = vec![
-            (2..=4, 6..=8),
-            (2..=3, 4..=5),
-            (5..=7, 7..=9),
-            (2..=8, 3..=7),
-            (6..=6, 4..=6),
-            (2..=6, 4..=8),
+            vec![2..=4, 6..=8],
+            vec![2..=3, 4..=5],
+            vec![5..=7, 7..=9],
+            vec![2..=8, 3..=7],
+            vec![6..=6, 4..=6],
+            vec![2..=6, 4..=8],
         ];
         assert_eq!(solve_part1(&input), 2);
     }
@@ -93,13 +764,336 @@ mod tests {
     #[test]
     pub fn test_solve_part2() {
         let input = vec![
-            (2..=4, 6..=8),
-            (2..=3, 4..=5),
-            (5..=7, 7..=9),
-            (2..=8, 3..=7),
-            (6..=6, 4..=6),
-            (2..=6, 4..=8),
+            vec![2..=4, 6..=8],
+            vec![2..=3, 4..=5],
+            vec![5..=7, 7..=9],
+            vec![2..=8, 3..=7],
+            vec![6..=6, 4..=6],
+            vec![2..=6, 4..=8],
         ];
         assert_eq!(solve_part2(&input), 4);
     }
+
+    #[test]
+    fn test_interval_set_insert() {
+        let set = IntervalSet::from_iter([8..=9, 1..=2, 3..=4, 12..=15, 6..=6, 11..=13]);
+        assert_eq!(
+            set.ranges().collect::<Vec<Section>>(),
+            vec![1..=4, 6..=6, 8..=9, 11..=15]
+        );
+        assert_eq!(set.len(), 12);
+
+        let set = IntervalSet::from_iter([1..=2, 6..=7, 2..=6]);
+        assert_eq!(set.ranges().collect::<Vec<Section>>(), vec![1..=7]);
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let set = IntervalSet::from(5..=3);
+        assert!(set.is_empty());
+        assert_eq!(set.len(), 0);
+    }
+
+    #[test]
+    fn test_interval_set_contains() {
+        let set = IntervalSet::from_iter([2..=4, 8..=10]);
+        assert!(set.contains(2));
+        assert!(set.contains(10));
+        assert!(!set.contains(1));
+        assert!(!set.contains(6));
+        assert!(!set.contains(11));
+
+        assert!(set.contains_set(&IntervalSet::from_iter([3..=4, 8..=8])));
+        assert!(set.contains_set(&IntervalSet::new()));
+        assert!(!set.contains_set(&IntervalSet::from(4..=8)));
+    }
+
+    #[test]
+    fn test_interval_set_operations() {
+        let a = IntervalSet::from_iter([1..=5, 10..=15]);
+        let b = IntervalSet::from_iter([4..=11, 14..=20]);
+
+        assert_eq!(a.union(&b).ranges().collect::<Vec<Section>>(), vec![1..=20]);
+        assert_eq!(
+            a.intersection(&b).ranges().collect::<Vec<Section>>(),
+            vec![4..=5, 10..=11, 14..=15]
+        );
+        assert_eq!(
+            a.difference(&b).ranges().collect::<Vec<Section>>(),
+            vec![1..=3, 12..=13]
+        );
+        assert_eq!(
+            b.difference(&a).ranges().collect::<Vec<Section>>(),
+            vec![6..=9, 16..=20]
+        );
+        assert_eq!(a.difference(&a), IntervalSet::new());
+    }
+
+    #[test]
+    fn test_parse_input_with_any_number_of_ranges() {
+        let input = r#"2-4
+2-3,4-5,1-9"#;
+        assert_eq!(
+            parse_input(input),
+            vec![vec![2..=4], vec![2..=3, 4..=5, 1..=9]]
+        );
+    }
+
+    #[test]
+    fn test_assignment_for_groups() {
+        let group: &[Section] = &[2..=4, 3..=8, 4..=4];
+        assert!(group.is_redundant());
+        assert!(group.has_overlap());
+        assert!(group.has_common_section());
+        assert_eq!(group.overlapping_pairs(), 3);
+
+        let group: &[Section] = &[2..=4, 3..=8, 7..=9];
+        assert!(!group.is_redundant());
+        assert!(group.has_overlap());
+        assert!(!group.has_common_section());
+        assert_eq!(group.overlapping_pairs(), 2);
+
+        let group: &[Section] = &[2..=4];
+        assert!(!group.is_redundant());
+        assert!(!group.has_overlap());
+        assert!(group.has_common_section());
+        assert_eq!(group.overlapping_pairs(), 0);
+    }
+
+    #[test]
+    fn test_report() {
+        let input = vec![vec![2..=4, 6..=8], vec![1..=5, 2..=3, 3..=9]];
+        assert_eq!(
+            report(&input),
+            vec![
+                GroupReport {
+                    line: 1,
+                    size: 2,
+                    is_redundant: false,
+                    has_common_section: false,
+                    overlapping_pairs: 0,
+                },
+                GroupReport {
+                    line: 2,
+                    size: 3,
+                    is_redundant: true,
+                    has_common_section: true,
+                    overlapping_pairs: 3,
+                },
+            ]
+        );
+        assert_eq!(
+            report(&input)[1].to_string(),
+            "line 2: 3 elves, redundant: true, common section: true, overlapping pairs: 3"
+        );
+    }
+
+    #[test]
+    fn test_coverage() {
+        let input = vec![
+            vec![2..=4, 6..=8],
+            vec![2..=3, 4..=5],
+            vec![5..=7, 7..=9],
+            vec![2..=8, 3..=7],
+            vec![6..=6, 4..=6],
+            vec![2..=6, 4..=8],
+            vec![12..=13],
+        ];
+        let coverage = coverage(&input, 4);
+
+        assert_eq!(coverage.uncovered, IntervalSet::from(10..=11));
+        assert_eq!(coverage.over_covered, IntervalSet::from(3..=7));
+        assert_eq!(coverage.max_depth, 8);
+        assert_eq!(coverage.max_depth_at, IntervalSet::from(6..=6));
+        assert_eq!(coverage.wasted, 34);
+    }
+
+    #[test]
+    fn test_coverage_with_no_sections() {
+        assert_eq!(coverage(&[], 1), Coverage::default());
+    }
+
+    #[test]
+    fn test_reassign_per_group() {
+        let input = vec![vec![2..=4, 6..=8], vec![5..=7, 7..=9], vec![2..=8, 3..=7]];
+        assert_eq!(
+            reassign(&input, 1..=99, ReassignMode::PerGroup),
+            Ok(Reassignment {
+                groups: vec![vec![2..=4, 6..=8], vec![4..=6, 7..=9], vec![1..=7, 8..=12]],
+                movement: 7,
+                optimal: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_reassign_picks_cheapest_order() {
+        let input = vec![vec![0..=9, 1..=1]];
+        let res = reassign(&input, 0..=99, ReassignMode::PerGroup).unwrap();
+        assert_eq!(res.movement, 2);
+        assert!(!res.groups[0].has_overlap());
+    }
+
+    #[test]
+    fn test_reassign_global() {
+        let input = vec![vec![2..=4, 6..=8], vec![3..=3]];
+        let res = reassign(&input, 1..=99, ReassignMode::Global).unwrap();
+        assert_eq!(res.movement, 2);
+        assert!(!res.groups.concat().has_overlap());
+
+        let input: Vec<Group> = (0..10).map(|i| vec![i..=i + 2]).collect();
+        let res = reassign(&input, 0..=99, ReassignMode::Global).unwrap();
+        assert!(!res.groups.concat().has_overlap());
+        assert!(res.optimal);
+
+        let input: Vec<Group> = (0..10).map(|i| vec![i..=i + i % 3]).collect();
+        let res = reassign(&input, 0..=99, ReassignMode::Global).unwrap();
+        assert!(!res.groups.concat().has_overlap());
+        assert!(!res.optimal);
+    }
+
+    #[test]
+    fn test_pack_equal_lengths_is_exact() {
+        let sections = vec![9..=11, 0..=2, 4..=6, 1..=3, 10..=12, 2..=4, 20..=22];
+        let order: Vec<usize> = (0..sections.len()).collect();
+        let best = order
+            .iter()
+            .copied()
+            .permutations(order.len())
+            .filter_map(|order| place_in_order(&sections, &order, &(0..=99)))
+            .map(|(_, cost)| cost)
+            .min();
+        let (_, cost, exact) = pack(&sections, &(0..=99)).unwrap();
+        assert!(exact);
+        assert_eq!(Some(cost), best);
+    }
+
+    #[test]
+    fn test_reassign_infeasible() {
+        let input = vec![vec![2..=4], vec![1..=5, 3..=7]];
+        assert_eq!(
+            reassign(&input, 1..=9, ReassignMode::PerGroup),
+            Err(ReassignError::Infeasible {
+                line: Some(2),
+                needed: 10,
+                available: 9
+            })
+        );
+        assert_eq!(
+            reassign(&input, 1..=12, ReassignMode::Global),
+            Err(ReassignError::Infeasible {
+                line: None,
+                needed: 13,
+                available: 12
+            })
+        );
+    }
+
+    #[test]
+    fn test_section_index() {
+        let input = vec![
+            vec![2..=4, 6..=8],
+            vec![2..=3, 4..=5],
+            vec![5..=7, 7..=9],
+            vec![2..=8, 3..=7],
+        ];
+        let index = SectionIndex::new(&input);
+        assert_eq!(index.len(), 8);
+
+        let found = |res: Vec<&IndexedSection>| -> Vec<(usize, usize)> {
+            res.iter().map(|x| (x.line, x.elf)).collect()
+        };
+        assert_eq!(
+            found(index.containing(4)),
+            vec![(1, 1), (4, 1), (4, 2), (2, 2)]
+        );
+        assert_eq!(
+            found(index.overlapping(8..=20)),
+            vec![(4, 1), (1, 2), (3, 2)]
+        );
+        assert_eq!(found(index.containing(1)), vec![]);
+        assert_eq!(found(index.containing(10)), vec![]);
+    }
+
+    #[test]
+    fn test_section_index_matches_linear_scan() {
+        let input: Vec<Group> = (0..50)
+            .map(|i| {
+                vec![
+                    (i * 7) % 31..=(i * 7) % 31 + i % 5,
+                    (i * 3) % 17..=(i * 3) % 17 + 9,
+                ]
+            })
+            .collect();
+        let index = SectionIndex::new(&input);
+
+        for start in 0..45 {
+            let range = start..=start + 3;
+            let expected = input
+                .iter()
+                .flatten()
+                .filter(|x| x.start() <= range.end() && x.end() >= range.start())
+                .count();
+            let found = index.overlapping(range);
+            assert_eq!(found.len(), expected);
+            assert!(found
+                .windows(2)
+                .all(|x| x[0].section.start() <= x[1].section.start()));
+        }
+    }
+
+    #[test]
+    fn test_parse_input_with_whitespace() {
+        assert_eq!(
+            parse_input(" 2 - 4 , 6-8 \n2-3,4-5"),
+            vec![vec![2..=4, 6..=8], vec![2..=3, 4..=5]]
+        );
+    }
+
+    #[test]
+    fn test_parse_input_with_reversed_ranges() {
+        let input = "2-4,6-8\n8-2,3-4";
+        assert_eq!(
+            parse_input_with(input, &ParseOptions::default()),
+            Err(ParseError {
+                line: 2,
+                token: "8-2".to_string(),
+                kind: ParseErrorKind::ReversedRange
+            })
+        );
+        assert_eq!(
+            parse_input_with(
+                input,
+                &ParseOptions {
+                    reversed_ranges: ReversedRanges::Normalize
+                }
+            ),
+            Ok(vec![vec![2..=4, 6..=8], vec![2..=8, 3..=4]])
+        );
+    }
+
+    #[test]
+    fn test_parse_input_with_malformed_ranges() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_input_with("2-4,68", &options),
+            Err(ParseError {
+                line: 1,
+                token: "68".to_string(),
+                kind: ParseErrorKind::MissingDash
+            })
+        );
+        assert_eq!(
+            parse_input_with("2-4\n2-x", &options),
+            Err(ParseError {
+                line: 2,
+                token: "2-x".to_string(),
+                kind: ParseErrorKind::InvalidNumber
+            })
+        );
+        assert_eq!(
+            parse_input_with("2-4,,6-8", &options)
+                .unwrap_err()
+                .to_string(),
+            "line 1: expected a range like `2-4` in \"\""
+        );
+    }
 }
@@ -2,8 +2,11 @@ use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
 
-pub type Crate = char;
+pub type Crate = String;
 
 pub type Stack = Vec<Crate>;
 
@@ -16,16 +19,55 @@ pub struct Move {
     to: usize,
 }
 
+impl Move {
+    // Stacks are 0-based here, unlike in the puzzle input
+    pub fn new(amount: usize, from: usize, to: usize) -> Self {
+        Self { amount, from, to }
+    }
+
+    pub fn amount(&self) -> usize {
+        self.amount
+    }
+
+    pub fn from(&self) -> usize {
+        self.from
+    }
+
+    pub fn to(&self) -> usize {
+        self.to
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.amount,
+            self.from + 1,
+            self.to + 1
+        )
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
-struct Ship {
+pub struct Ship {
     cargo: Cargo,
 }
 
 impl Ship {
-    fn top_cargo(&self) -> String {
+    pub fn new(cargo: Cargo) -> Self {
+        Self { cargo }
+    }
+
+    pub fn cargo(&self) -> &Cargo {
+        &self.cargo
+    }
+
+    pub fn top_cargo(&self) -> String {
         self.cargo
             .iter()
-            .map(|stack| stack.last().unwrap_or(&' '))
+            .map(|stack| stack.last().map(|x| x.as_str()).unwrap_or(" "))
             .collect()
     }
 
@@ -34,113 +76,920 @@ impl Ship {
         self.cargo[to].push(cargo_crate);
     }
 
-    fn move_crate_stack(&mut self, from: usize, to: usize, size: usize) {
-        let new_len = self.cargo[from].len() - size;
-
+    fn stacks_mut(&mut self, from: usize, to: usize) -> (&mut Stack, &mut Stack) {
         // NOTE: this is go get away with Rust's borrow checker, since we need to do multiple
         // .     mutable borrows and it doesn't like it…
         let stacks = self.cargo.split_at_mut(std::cmp::max(from, to));
-        let (from_stack, to_stack) = if from > to {
+        if from > to {
             (&mut stacks.1[0], &mut stacks.0[to])
         } else {
             (&mut stacks.0[from], &mut stacks.1[0])
-        };
+        }
+    }
+
+    // Moves all the crates with a single splice (reversing them when `keep_order` is false, as if
+    // they had been moved one by one), so huge moves cost a memmove instead of a loop per crate.
+    // This is the primitive crane models are built on; like `Crane::operate`, it expects a move
+    // that passes `Move::validate` and panics otherwise.
+    pub fn lift(&mut self, from: usize, to: usize, amount: usize, keep_order: bool) {
+        if from == to {
+            return;
+        }
+        let new_len = self.cargo[from].len() - amount;
+        let (from_stack, to_stack) = self.stacks_mut(from, to);
 
-        for cargo_crate in from_stack.drain(new_len..) {
-            to_stack.push(cargo_crate)
+        if keep_order {
+            to_stack.extend(from_stack.drain(new_len..));
+        } else {
+            to_stack.extend(from_stack.drain(new_len..).rev());
         }
     }
 
-    fn operate_crane_v1(&mut self, moves: &[Move]) {
+    pub fn operate<C: Crane + ?Sized>(&mut self, crane: &mut C, moves: &[Move]) {
         for crane_move in moves {
-            for _ in 0..crane_move.amount {
-                self.move_crate(crane_move.from, crane_move.to);
+            crane.operate(self, crane_move);
+        }
+    }
+
+    // Checks every move against the current state before operating the crane. With the `Fail`
+    // policy the first invalid move is returned as an error; otherwise invalid moves are
+    // skipped or clamped, and returned once the simulation is over.
+    pub fn try_operate<C: Crane + ?Sized>(
+        &mut self,
+        crane: &mut C,
+        moves: &[Move],
+        policy: InvalidMovePolicy,
+    ) -> Result<Vec<SimulationError>, SimulationError> {
+        let mut errors = vec![];
+        for (move_index, crane_move) in moves.iter().enumerate() {
+            errors.extend(self.try_step(crane, move_index, crane_move, policy)?);
+        }
+        Ok(errors)
+    }
+
+    // A single move of `try_operate`, returning the error it recovered from, if any
+    fn try_step<C: Crane + ?Sized>(
+        &mut self,
+        crane: &mut C,
+        move_index: usize,
+        crane_move: &Move,
+        policy: InvalidMovePolicy,
+    ) -> Result<Option<SimulationError>, SimulationError> {
+        let reason = match crane_move.validate(&self.cargo) {
+            Ok(()) => {
+                crane.operate(self, crane_move);
+                return Ok(None);
+            }
+            Err(reason) => reason,
+        };
+
+        match (policy, &reason) {
+            (InvalidMovePolicy::Fail, _) => {
+                return Err(SimulationError { move_index, reason });
             }
+            (InvalidMovePolicy::Clamp, InvalidMove::NotEnoughCrates { available, .. }) => {
+                let clamped = Move {
+                    amount: *available,
+                    ..*crane_move
+                };
+                crane.operate(self, &clamped);
+            }
+            _ => {}
         }
+        Ok(Some(SimulationError { move_index, reason }))
     }
+}
 
-    fn operate_crane_v2(&mut self, moves: &[Move]) {
-        for crane_move in moves {
-            self.move_crate_stack(crane_move.from, crane_move.to, crane_move.amount)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InvalidMove {
+    StackOutOfRange { stack: usize, stacks: usize },
+    NotEnoughCrates { requested: usize, available: usize },
+}
+
+impl fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::StackOutOfRange { stack, stacks } => write!(
+                f,
+                "stack {} does not exist (there are {} stacks)",
+                stack + 1,
+                stacks
+            ),
+            Self::NotEnoughCrates {
+                requested,
+                available,
+            } => write!(
+                f,
+                "cannot move {} crates from a stack with {}",
+                requested, available
+            ),
         }
     }
 }
 
-#[aoc_generator(day5)]
-pub fn parse_input(input: &str) -> (Cargo, Vec<Move>) {
-    let mut chunks = input.split("\n\n");
-    let cargo = parse_cargo(chunks.next().unwrap());
-    let moves = parse_moves(chunks.next().unwrap());
-    (cargo, moves)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SimulationError {
+    // 0-based position of the move in the list
+    pub move_index: usize,
+    pub reason: InvalidMove,
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move #{}: {}", self.move_index + 1, self.reason)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum InvalidMovePolicy {
+    #[default]
+    Fail,
+    Skip,
+    // moves with too few crates move as many as there are (and out of range stacks are skipped)
+    Clamp,
+}
+
+impl Move {
+    pub fn validate(&self, cargo: &Cargo) -> Result<(), InvalidMove> {
+        for stack in [self.from, self.to] {
+            if stack >= cargo.len() {
+                return Err(InvalidMove::StackOutOfRange {
+                    stack,
+                    stacks: cargo.len(),
+                });
+            }
+        }
+
+        let available = cargo[self.from].len();
+        if self.amount > available {
+            return Err(InvalidMove::NotEnoughCrates {
+                requested: self.amount,
+                available,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Ship {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render_cargo(&self.cargo))
+    }
+}
+
+pub trait Crane {
+    fn operate(&mut self, ship: &mut Ship, crane_move: &Move);
+}
+
+// Moves crates one at a time
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+        for _ in 0..crane_move.amount {
+            ship.move_crate(crane_move.from, crane_move.to);
+        }
+    }
+}
+
+// Moves several crates at once, keeping their order
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+        ship.lift(crane_move.from, crane_move.to, crane_move.amount, true)
+    }
+}
+
+// Same behavior as the CrateMover 9000, but moving all the crates of a move at once
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct BulkCrateMover9000;
+
+impl Crane for BulkCrateMover9000 {
+    fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+        ship.lift(crane_move.from, crane_move.to, crane_move.amount, false)
+    }
+}
+
+// Like the CrateMover 9001, but it can only lift up to `capacity` crates at once, so larger
+// moves are split into several batches
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LimitedCapacityCrane {
+    pub capacity: usize,
+}
+
+impl Crane for LimitedCapacityCrane {
+    fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+        let mut remaining = crane_move.amount;
+        while remaining > 0 {
+            let batch = remaining.min(self.capacity.max(1));
+            ship.lift(crane_move.from, crane_move.to, batch, true);
+            remaining -= batch;
+        }
+    }
+}
+
+// Lifts whole moves at once, but every other batch ends up upside down
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct InvertingCrane {
+    batches: usize,
+}
+
+impl Crane for InvertingCrane {
+    fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+        if self.batches.is_multiple_of(2) {
+            CrateMover9001.operate(ship, crane_move);
+        } else {
+            CrateMover9000.operate(ship, crane_move);
+        }
+        self.batches += 1;
+    }
+}
+
+// Crane models as named in the CLI: `9000`, `9001`, `9000-bulk`, `limited:<capacity>` and
+// `inverting`
+pub fn crane_from_name(name: &str) -> Option<Box<dyn Crane>> {
+    match name.split_once(':') {
+        Some(("limited", capacity)) => match capacity.parse::<usize>() {
+            Ok(capacity) if capacity > 0 => Some(Box::new(LimitedCapacityCrane { capacity })),
+            _ => None,
+        },
+        Some(_) => None,
+        None => match name {
+            "9000" => Some(Box::new(CrateMover9000)),
+            "9001" => Some(Box::new(CrateMover9001)),
+            "9000-bulk" => Some(Box::new(BulkCrateMover9000)),
+            "inverting" => Some(Box::new(InvertingCrane::default())),
+            _ => None,
+        },
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CrateChange {
+    pub label: Crate,
+    // (stack, level) pairs, both 0-based and with level 0 being the bottom of the stack
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+}
+
+impl fmt::Display for CrateChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] stack {} level {} -> stack {} level {}",
+            self.label,
+            self.from.0 + 1,
+            self.from.1 + 1,
+            self.to.0 + 1,
+            self.to.1 + 1
+        )
+    }
+}
+
+const CHECKPOINT_INTERVAL: usize = 64;
+
+// Operation log for a ship. The cargo (and the crane, since some of them have state) is saved
+// every `CHECKPOINT_INTERVAL` moves, so any past state is rebuilt by replaying a few moves.
+#[derive(Debug, Clone)]
+pub struct ShipHistory<C: Crane + Clone> {
+    ship: Ship,
+    crane: C,
+    moves: Vec<Move>,
+    position: usize,
+    checkpoints: Vec<(Cargo, C)>,
+}
+
+impl<C: Crane + Clone> ShipHistory<C> {
+    pub fn new(ship: Ship, crane: C) -> Self {
+        Self {
+            checkpoints: vec![(ship.cargo.clone(), crane.clone())],
+            ship,
+            crane,
+            moves: vec![],
+            position: 0,
+        }
+    }
+
+    pub fn ship(&self) -> &Ship {
+        &self.ship
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    // Amount of moves currently applied to the ship
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Operates the crane, discarding any moves that had been undone. Invalid moves are rejected
+    // before touching the history, so every recorded move can be replayed.
+    pub fn apply(&mut self, crane_move: Move) -> Result<(), InvalidMove> {
+        crane_move.validate(&self.ship.cargo)?;
+        self.moves.truncate(self.position);
+        self.checkpoints
+            .truncate(self.position / CHECKPOINT_INTERVAL + 1);
+
+        self.crane.operate(&mut self.ship, &crane_move);
+        self.moves.push(crane_move);
+        self.position += 1;
+
+        if self.position.is_multiple_of(CHECKPOINT_INTERVAL) {
+            self.checkpoints
+                .push((self.ship.cargo.clone(), self.crane.clone()));
+        }
+        Ok(())
+    }
+
+    // Stops at the first invalid move, keeping the ones before it applied
+    pub fn apply_all(&mut self, moves: &[Move]) -> Result<(), SimulationError> {
+        for (move_index, crane_move) in moves.iter().enumerate() {
+            self.apply(*crane_move)
+                .map_err(|reason| SimulationError { move_index, reason })?;
+        }
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Option<Move> {
+        if self.position == 0 {
+            return None;
+        }
+        self.seek(self.position - 1);
+        Some(self.moves[self.position])
+    }
+
+    pub fn redo(&mut self) -> Option<Move> {
+        let crane_move = *self.moves.get(self.position)?;
+        self.crane.operate(&mut self.ship, &crane_move);
+        self.position += 1;
+        Some(crane_move)
+    }
+
+    // Rewinds or replays the recorded moves until `move_index` of them are applied
+    pub fn seek(&mut self, move_index: usize) {
+        let (ship, crane) = self.replay_until(move_index.min(self.moves.len()));
+        self.ship = ship;
+        self.crane = crane;
+        self.position = move_index.min(self.moves.len());
+    }
+
+    // The ship after the first `move_index` moves (`state_at(0)` is the initial state)
+    pub fn state_at(&self, move_index: usize) -> Option<Ship> {
+        if move_index > self.moves.len() {
+            return None;
+        }
+        Some(self.replay_until(move_index).0)
+    }
+
+    fn replay_until(&self, move_index: usize) -> (Ship, C) {
+        let checkpoint = move_index / CHECKPOINT_INTERVAL;
+        let (cargo, crane) = &self.checkpoints[checkpoint];
+        let mut ship = Ship::new(cargo.clone());
+        let mut crane = crane.clone();
+        for crane_move in &self.moves[checkpoint * CHECKPOINT_INTERVAL..move_index] {
+            crane.operate(&mut ship, crane_move);
+        }
+        (ship, crane)
+    }
+
+    // Crates that ended up somewhere else between two points of the history. Crates are told
+    // apart by replaying the moves with unique labels, so repeated labels are tracked correctly.
+    pub fn diff(&self, from_index: usize, to_index: usize) -> Option<Vec<CrateChange>> {
+        if from_index > to_index {
+            let changes = self.diff(to_index, from_index)?;
+            return Some(
+                changes
+                    .into_iter()
+                    .map(|x| CrateChange {
+                        label: x.label,
+                        from: x.to,
+                        to: x.from,
+                    })
+                    .collect(),
+            );
+        }
+        if to_index > self.moves.len() {
+            return None;
+        }
+
+        let (start, mut crane) = self.replay_until(from_index);
+        let mut positions = vec![];
+        let ids: Cargo = start
+            .cargo
+            .iter()
+            .enumerate()
+            .map(|(stack, crates)| {
+                (0..crates.len())
+                    .map(|level| {
+                        positions.push((stack, level));
+                        (positions.len() - 1).to_string()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut ship = Ship::new(ids);
+        for crane_move in &self.moves[from_index..to_index] {
+            crane.operate(&mut ship, crane_move);
+        }
+
+        let mut changes = vec![];
+        for (stack, crates) in ship.cargo.iter().enumerate() {
+            for (level, id) in crates.iter().enumerate() {
+                let from = positions[id.parse::<usize>().unwrap()];
+                if from != (stack, level) {
+                    changes.push(CrateChange {
+                        label: start.cargo[from.0][from.1].clone(),
+                        from,
+                        to: (stack, level),
+                    });
+                }
+            }
+        }
+        Some(changes)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PlanError {
+    MismatchedStacks { from: usize, to: usize },
+    MismatchedCrates,
+    NotFound { max_moves: usize },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MismatchedStacks { from, to } => {
+                write!(f, "cannot go from {} stacks to {}", from, to)
+            }
+            Self::MismatchedCrates => write!(f, "both arrangements must have the same crates"),
+            Self::NotFound { max_moves } => {
+                write!(f, "no plan found with up to {} moves", max_moves)
+            }
+        }
+    }
 }
 
-fn parse_cargo(input: &str) -> Cargo {
-    let mut cargo = Cargo::new();
+// Lower bound of the moves left: every stack that isn't a prefix of its target needs to lose
+// crates, and every stack whose common prefix with its target is shorter than the target needs
+// to get some. A move does one of each.
+fn misplaced_heuristic(cargo: &Cargo, target: &Cargo) -> usize {
+    let (mut sources, mut destinations) = (0, 0);
+    for (stack, goal) in cargo.iter().zip(target) {
+        let common = stack.iter().zip(goal).take_while(|(a, b)| a == b).count();
+        if common < stack.len() {
+            sources += 1;
+        }
+        if common < goal.len() {
+            destinations += 1;
+        }
+    }
+    sources.max(destinations)
+}
+
+struct PlanNode<C> {
+    cargo: Cargo,
+    crane: C,
+    parent: Option<(usize, Move)>,
+}
+
+// A* search for the shortest list of moves that turns `start` into `target` with the given crane.
+// The crane is cloned along every path; since visited arrangements are pruned regardless of the
+// crane's state, plans are only guaranteed to be the shortest for stateless cranes.
+pub fn plan_moves<C: Crane + Clone>(
+    start: &Cargo,
+    target: &Cargo,
+    crane: &C,
+    max_moves: usize,
+) -> Result<Vec<Move>, PlanError> {
+    if start.len() != target.len() {
+        return Err(PlanError::MismatchedStacks {
+            from: start.len(),
+            to: target.len(),
+        });
+    }
+    let sorted = |cargo: &Cargo| {
+        let mut crates: Vec<Crate> = cargo.iter().flatten().cloned().collect();
+        crates.sort();
+        crates
+    };
+    if sorted(start) != sorted(target) {
+        return Err(PlanError::MismatchedCrates);
+    }
 
-    for line in input.lines().rev() {
-        for i in 0..((line.len() + 1) / 4) {
-            if let None = cargo.get(i) {
-                cargo.push(vec![]);
+    let mut nodes = vec![PlanNode {
+        cargo: start.clone(),
+        crane: crane.clone(),
+        parent: None,
+    }];
+    let mut best: HashMap<Cargo, usize> = HashMap::from([(start.clone(), 0)]);
+    let mut queue = BinaryHeap::from([Reverse((misplaced_heuristic(start, target), 0, 0))]);
+
+    while let Some(Reverse((_, cost, index))) = queue.pop() {
+        if best.get(&nodes[index].cargo).is_some_and(|&x| x < cost) {
+            continue;
+        }
+        if &nodes[index].cargo == target {
+            let mut moves = vec![];
+            let mut current = index;
+            while let Some((parent, crane_move)) = nodes[current].parent {
+                moves.push(crane_move);
+                current = parent;
             }
-            if let Some(x) = line.chars().nth((i * 4) + 1) {
-                if x.is_alphabetic() {
-                    cargo[i].push(x);
+            moves.reverse();
+            return Ok(moves);
+        }
+        if cost == max_moves {
+            continue;
+        }
+
+        let stacks = nodes[index].cargo.len();
+        for from in 0..stacks {
+            for to in (0..stacks).filter(|&to| to != from) {
+                for amount in 1..=nodes[index].cargo[from].len() {
+                    let crane_move = Move { amount, from, to };
+                    let mut ship = Ship::new(nodes[index].cargo.clone());
+                    let mut crane = nodes[index].crane.clone();
+                    crane.operate(&mut ship, &crane_move);
+
+                    let next_cost = cost + 1;
+                    if best.get(&ship.cargo).is_some_and(|&x| x <= next_cost) {
+                        continue;
+                    }
+                    best.insert(ship.cargo.clone(), next_cost);
+                    let estimate = next_cost + misplaced_heuristic(&ship.cargo, target);
+                    if estimate > max_moves {
+                        continue;
+                    }
+
+                    queue.push(Reverse((estimate, next_cost, nodes.len())));
+                    nodes.push(PlanNode {
+                        cargo: ship.cargo,
+                        crane,
+                        parent: Some((index, crane_move)),
+                    });
                 }
             }
         }
     }
 
-    cargo
+    Err(PlanError::NotFound { max_moves })
+}
+
+// Draws the cargo the same way the puzzle input does, numbered footer included. Columns get
+// wider when there are crate labels longer than one character.
+pub fn render_cargo(cargo: &Cargo) -> String {
+    let height = cargo.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let width = cargo
+        .iter()
+        .flatten()
+        .map(|x| x.chars().count() + 2)
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    let mut lines: Vec<String> = (0..height)
+        .rev()
+        .map(|level| {
+            cargo
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(x) => format!("{:^width$}", format!("[{}]", x), width = width),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect();
+    lines.push(
+        (1..=cargo.len())
+            .map(|i| format!("{:^width$}", i, width = width))
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+    lines.join("\n")
+}
+
+const SVG_CRATE_SIZE: usize = 40;
+const SVG_GAP: usize = 10;
+
+fn escape_xml(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
-fn parse_moves(input: &str) -> Vec<Move> {
+pub fn render_cargo_svg(cargo: &Cargo) -> String {
+    let height = cargo.iter().map(|stack| stack.len()).max().unwrap_or(0) + 1;
+    let step = SVG_CRATE_SIZE + SVG_GAP;
+    let (width, height) = (cargo.len() * step + SVG_GAP, height * step + SVG_GAP);
+
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    );
+    out += &format!(
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        width, height
+    );
+    for (i, stack) in cargo.iter().enumerate() {
+        let x = SVG_GAP + i * step;
+        let footer_y = height - step;
+        out += &format!(
+            r#"<text x="{}" y="{}" text-anchor="middle" font-family="monospace">{}</text>"#,
+            x + SVG_CRATE_SIZE / 2,
+            footer_y + SVG_CRATE_SIZE / 2,
+            i + 1
+        );
+        for (level, cargo_crate) in stack.iter().enumerate() {
+            let y = footer_y - (level + 1) * step;
+            out += &format!(
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#c8a165" stroke="black"/>"##,
+                x, y, SVG_CRATE_SIZE, SVG_CRATE_SIZE
+            );
+            out += &format!(
+                r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-family="monospace">{}</text>"#,
+                x + SVG_CRATE_SIZE / 2,
+                y + SVG_CRATE_SIZE / 2,
+                escape_xml(cargo_crate)
+            );
+        }
+    }
+    out += "</svg>";
+    out
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    pub moves_applied: usize,
+    pub cargo: Cargo,
+    // invalid moves skipped or clamped since the previous frame
+    pub errors: Vec<SimulationError>,
+}
+
+impl Frame {
+    pub fn drawing(&self) -> String {
+        render_cargo(&self.cargo)
+    }
+
+    pub fn svg(&self) -> String {
+        render_cargo_svg(&self.cargo)
+    }
+}
+
+// Lazily simulates the moves, so frames can be shown as soon as they are ready and only one
+// snapshot of the cargo is alive at a time
+pub struct Animation<'a, C: Crane + ?Sized> {
+    ship: Ship,
+    moves: &'a [Move],
+    crane: &'a mut C,
+    every: usize,
+    policy: InvalidMovePolicy,
+    // `None` before the first frame, and once the animation is over
+    next_move: Option<usize>,
+    started: bool,
+}
+
+impl<C: Crane + ?Sized> Iterator for Animation<'_, C> {
+    type Item = Result<Frame, SimulationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            self.next_move = Some(0);
+            return Some(Ok(Frame {
+                moves_applied: 0,
+                cargo: self.ship.cargo.clone(),
+                errors: vec![],
+            }));
+        }
+
+        let mut i = self.next_move?;
+        if i >= self.moves.len() {
+            self.next_move = None;
+            return None;
+        }
+
+        let mut errors = vec![];
+        while i < self.moves.len() {
+            match self
+                .ship
+                .try_step(self.crane, i, &self.moves[i], self.policy)
+            {
+                Ok(error) => errors.extend(error),
+                Err(err) => {
+                    self.next_move = None;
+                    return Some(Err(err));
+                }
+            }
+            i += 1;
+            if i.is_multiple_of(self.every) {
+                break;
+            }
+        }
+
+        self.next_move = Some(i);
+        Some(Ok(Frame {
+            moves_applied: i,
+            cargo: self.ship.cargo.clone(),
+            errors,
+        }))
+    }
+}
+
+// Snapshots of the cargo before any move, after every `every` moves and after the last one.
+// Moves are validated like in `Ship::try_operate`: recovered errors come with the next frame, and
+// the animation ends after the first unrecovered one.
+pub fn animate<'a, C: Crane + ?Sized>(
+    cargo: &Cargo,
+    moves: &'a [Move],
+    crane: &'a mut C,
+    every: usize,
+    policy: InvalidMovePolicy,
+) -> Animation<'a, C> {
+    Animation {
+        ship: Ship::new(cargo.clone()),
+        moves,
+        crane,
+        every: every.max(1),
+        policy,
+        next_move: None,
+        started: false,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseErrorKind {
+    MissingFooter,
+    EmptyLabel,
+    MissingMoves,
+    MissingNumbers,
+    InvalidNumber,
+    InvalidStack,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    // 1-based
+    pub line: usize,
+    pub token: String,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            ParseErrorKind::MissingFooter => "expected stack numbers below the drawing",
+            ParseErrorKind::EmptyLabel => "crates need a label",
+            ParseErrorKind::MissingMoves => "expected a blank line between the cargo and the moves",
+            ParseErrorKind::MissingNumbers => "expected a move like `move 1 from 2 to 3`",
+            ParseErrorKind::InvalidNumber => "invalid number",
+            ParseErrorKind::InvalidStack => "stacks are numbered from 1",
+        };
+        write!(f, "line {}: {} in {:?}", self.line, reason, self.token)
+    }
+}
+
+pub fn try_parse_input(input: &str) -> Result<(Cargo, Vec<Move>), ParseError> {
+    let (cargo, moves) = input.split_once("\n\n").ok_or_else(|| ParseError {
+        line: input.lines().count(),
+        token: input.lines().last().unwrap_or("").to_string(),
+        kind: ParseErrorKind::MissingMoves,
+    })?;
+    // moves start after the drawing and the blank line below it
+    let first_line = cargo.lines().count() + 2;
+    Ok((parse_cargo(cargo)?, parse_moves(moves, first_line)?))
+}
+
+#[aoc_generator(day5)]
+pub fn parse_input(input: &str) -> (Cargo, Vec<Move>) {
+    try_parse_input(input).unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn char_offset(line: &str, byte_offset: usize) -> usize {
+    line[..byte_offset].chars().count()
+}
+
+// Stacks are located through the numbered footer, so there can be any amount of them, and
+// crates (with labels of any width) are assigned to the stack whose number is closest
+fn parse_cargo(input: &str) -> Result<Cargo, ParseError> {
+    lazy_static! {
+        static ref NUMBER_RE: Regex = Regex::new(r"\d+").unwrap();
+        static ref CRATE_RE: Regex = Regex::new(r"\[([^\]]*)\]").unwrap();
+    }
+
+    let lines: Vec<&str> = input.lines().collect();
+    let (footer, drawing) = lines.split_last().unwrap_or((&"", &[]));
+    // NOTE: centers are doubled, so they are always integers
+    let centers: Vec<usize> = NUMBER_RE
+        .find_iter(footer)
+        .map(|x| char_offset(footer, x.start()) + char_offset(footer, x.end()))
+        .collect();
+    if centers.is_empty() {
+        return Err(ParseError {
+            line: lines.len().max(1),
+            token: footer.to_string(),
+            kind: ParseErrorKind::MissingFooter,
+        });
+    }
+
+    let mut cargo: Cargo = vec![vec![]; centers.len()];
+    for (i, line) in drawing.iter().enumerate().rev() {
+        for captures in CRATE_RE.captures_iter(line) {
+            let token = captures.get(0).unwrap();
+            let label = captures[1].trim();
+            if label.is_empty() {
+                return Err(ParseError {
+                    line: i + 1,
+                    token: token.as_str().to_string(),
+                    kind: ParseErrorKind::EmptyLabel,
+                });
+            }
+            let center = char_offset(line, token.start()) + char_offset(line, token.end());
+            let stack = (0..centers.len())
+                .min_by_key(|&i| centers[i].abs_diff(center))
+                .unwrap();
+            cargo[stack].push(label.to_string());
+        }
+    }
+
+    Ok(cargo)
+}
+
+// Like the original parser, the first three numbers of every line are the amount and the stacks,
+// whatever the words around them
+fn parse_moves(input: &str, first_line: usize) -> Result<Vec<Move>, ParseError> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"\d+").unwrap();
     }
 
     input
         .lines()
-        .into_iter()
-        .map(|line| {
-            let numbers: Vec<usize> = RE
+        .enumerate()
+        .map(|(i, line)| {
+            let error = |kind| ParseError {
+                line: first_line + i,
+                token: line.to_string(),
+                kind,
+            };
+            let numbers = RE
                 .find_iter(line)
-                .map(|x| x.as_str().parse::<usize>().unwrap())
-                .collect();
-            Move {
-                amount: numbers[0],
-                from: (numbers[1] - 1),
-                to: (numbers[2] - 1),
+                .take(3)
+                .map(|x| x.as_str().parse::<usize>())
+                .collect::<Result<Vec<usize>, _>>()
+                .map_err(|_| error(ParseErrorKind::InvalidNumber))?;
+            match numbers[..] {
+                [_, 0, _] | [_, _, 0] => Err(error(ParseErrorKind::InvalidStack)),
+                [amount, from, to] => Ok(Move {
+                    amount,
+                    from: from - 1,
+                    to: to - 1,
+                }),
+                _ => Err(error(ParseErrorKind::MissingNumbers)),
             }
         })
         .collect()
 }
 
+pub fn solve_with_crane<C: Crane + ?Sized>(input: &(Cargo, Vec<Move>), crane: &mut C) -> String {
+    let mut ship = Ship::new(input.0.clone());
+    ship.operate(crane, &input.1);
+    ship.top_cargo()
+}
+
 #[aoc(day5, part1)]
 pub fn solve_part1(input: &(Cargo, Vec<Move>)) -> String {
-    let mut ship = Ship {
-        cargo: input.0.clone(),
-    };
-    ship.operate_crane_v1(&input.1);
-    ship.top_cargo()
+    solve_with_crane(input, &mut CrateMover9000)
 }
 
 #[aoc(day5, part2)]
 pub fn solve_part2(input: &(Cargo, Vec<Move>)) -> String {
-    let mut ship = Ship {
-        cargo: input.0.clone(),
-    };
-    ship.operate_crane_v2(&input.1);
-    ship.top_cargo()
+    solve_with_crane(input, &mut CrateMover9001)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::Lcg;
+
+    fn to_cargo(stacks: Vec<Vec<char>>) -> Cargo {
+        stacks
+            .into_iter()
+            .map(|stack| stack.into_iter().map(|x| x.to_string()).collect())
+            .collect()
+    }
 
     #[test]
     fn test_ship_top_cargo() {
         let ship = Ship {
-            cargo: vec![vec!['A', 'B'], vec!['C'], vec!['D']],
+            cargo: to_cargo(vec![vec!['A', 'B'], vec!['C'], vec!['D']]),
         };
         assert_eq!(ship.top_cargo(), "BCD");
     }
@@ -148,18 +997,21 @@ mod tests {
     #[test]
     fn test_ship_move_crane() {
         let mut ship = Ship {
-            cargo: vec![vec!['A', 'B'], vec!['C'], vec![]],
+            cargo: to_cargo(vec![vec!['A', 'B'], vec!['C'], vec![]]),
         };
 
         ship.move_crate(0, 1);
 
-        assert_eq!(ship.cargo, [vec!['A'], vec!['C', 'B'], vec![]]);
+        assert_eq!(
+            ship.cargo,
+            to_cargo(vec![vec!['A'], vec!['C', 'B'], vec![]])
+        );
     }
 
     #[test]
     fn test_solve_part1() {
         let input = (
-            vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']],
+            to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]),
             vec![
                 Move {
                     amount: 1,
@@ -189,7 +1041,7 @@ mod tests {
     #[test]
     fn test_solve_part2() {
         let input = (
-            vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']],
+            to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]),
             vec![
                 Move {
                     amount: 1,
@@ -230,7 +1082,7 @@ move 1 from 1 to 2"#;
         assert_eq!(
             parse_input(input),
             (
-                vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P'],],
+                to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P'],]),
                 vec![
                     Move {
                         amount: 1,
@@ -256,4 +1108,713 @@ move 1 from 1 to 2"#;
             )
         )
     }
+
+    #[test]
+    fn test_try_parse_input() {
+        let cargo = "[A]\n 1 \n\n";
+        assert_eq!(
+            try_parse_input(&format!("{}MOVE 2 FROM 1 TO 1 ", cargo)),
+            Ok((
+                to_cargo(vec![vec!['A']]),
+                vec![Move {
+                    amount: 2,
+                    from: 0,
+                    to: 0
+                }]
+            ))
+        );
+        assert_eq!(
+            try_parse_input(&format!("{}move 1 from 1 to 1\nmove 1 from 1", cargo)),
+            Err(ParseError {
+                line: 5,
+                token: "move 1 from 1".to_string(),
+                kind: ParseErrorKind::MissingNumbers
+            })
+        );
+        assert_eq!(
+            try_parse_input(&format!("{}move 1 from 0 to 1", cargo)),
+            Err(ParseError {
+                line: 4,
+                token: "move 1 from 0 to 1".to_string(),
+                kind: ParseErrorKind::InvalidStack
+            })
+        );
+        assert_eq!(
+            try_parse_input(&format!("{}move 99999999999999999999 from 1 to 1", cargo)),
+            Err(ParseError {
+                line: 4,
+                token: "move 99999999999999999999 from 1 to 1".to_string(),
+                kind: ParseErrorKind::InvalidNumber
+            })
+        );
+        assert_eq!(
+            try_parse_input("[A]\n 1 "),
+            Err(ParseError {
+                line: 2,
+                token: " 1 ".to_string(),
+                kind: ParseErrorKind::MissingMoves
+            })
+        );
+    }
+
+    #[test]
+    fn test_limited_capacity_crane() {
+        let mut ship = Ship::new(to_cargo(vec![vec!['A', 'B', 'C', 'D', 'E'], vec![]]));
+        let crane_move = Move {
+            amount: 5,
+            from: 0,
+            to: 1,
+        };
+        ship.operate(&mut LimitedCapacityCrane { capacity: 2 }, &[crane_move]);
+        assert_eq!(
+            ship.cargo(),
+            &to_cargo(vec![vec![], vec!['D', 'E', 'B', 'C', 'A']])
+        );
+    }
+
+    #[test]
+    fn test_inverting_crane() {
+        let mut ship = Ship::new(to_cargo(vec![vec!['A', 'B', 'C', 'D'], vec![]]));
+        let crane_move = Move {
+            amount: 2,
+            from: 0,
+            to: 1,
+        };
+        ship.operate(&mut InvertingCrane::default(), &[crane_move, crane_move]);
+        assert_eq!(
+            ship.cargo(),
+            &to_cargo(vec![vec![], vec!['C', 'D', 'B', 'A']])
+        );
+    }
+
+    #[test]
+    fn test_crane_from_name() {
+        let input = (
+            to_cargo(vec![vec!['A', 'B', 'C'], vec![]]),
+            vec![Move {
+                amount: 3,
+                from: 0,
+                to: 1,
+            }],
+        );
+        let solve = |name: &str| solve_with_crane(&input, crane_from_name(name).unwrap().as_mut());
+
+        assert_eq!(solve("9000"), " A");
+        assert_eq!(solve("9001"), " C");
+        assert_eq!(solve("limited:2"), " A");
+        assert_eq!(solve("inverting"), " C");
+        assert!(crane_from_name("limited:0").is_none());
+        assert!(crane_from_name("9002").is_none());
+    }
+
+    #[test]
+    fn test_render_cargo() {
+        let cargo = to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+        assert_eq!(
+            render_cargo(&cargo),
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+        assert_eq!(render_cargo(&to_cargo(vec![vec![], vec![]])), " 1   2 ");
+    }
+
+    #[test]
+    fn test_render_cargo_svg() {
+        let svg = render_cargo_svg(&to_cargo(vec![vec!['&'], vec![]]));
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert!(svg.contains(">&amp;</text>"));
+    }
+
+    #[test]
+    fn test_animate() {
+        let cargo = to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+        let moves = parse_moves(
+            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1",
+            1,
+        )
+        .unwrap();
+        let frames: Vec<Frame> = animate(
+            &cargo,
+            &moves,
+            &mut CrateMover9000,
+            2,
+            InvalidMovePolicy::Fail,
+        )
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert!(frames.iter().all(|x| x.errors.is_empty()));
+
+        assert_eq!(
+            frames
+                .iter()
+                .map(|x| x.moves_applied)
+                .collect::<Vec<usize>>(),
+            vec![0, 2, 3]
+        );
+        assert_eq!(frames[0].cargo, cargo);
+        assert_eq!(
+            frames[1].drawing(),
+            "        [Z]\n        [N]\n    [C] [D]\n    [M] [P]\n 1   2   3 "
+        );
+        assert_eq!(
+            frames[2].cargo,
+            to_cargo(vec![vec!['C', 'M'], vec![], vec!['P', 'D', 'N', 'Z']])
+        );
+    }
+
+    #[test]
+    fn test_animate_invalid_moves() {
+        let cargo = to_cargo(vec![vec!['A'], vec!['B']]);
+        let moves = parse_moves("move 2 from 1 to 2\nmove 1 from 3 to 1", 1).unwrap();
+        let mut crane = CrateMover9000;
+        let mut frames = animate(&cargo, &moves, &mut crane, 1, InvalidMovePolicy::Fail);
+        assert_eq!(frames.next().map(|x| x.is_ok()), Some(true));
+        assert_eq!(
+            frames.next(),
+            Some(Err(SimulationError {
+                move_index: 0,
+                reason: InvalidMove::NotEnoughCrates {
+                    requested: 2,
+                    available: 1
+                }
+            }))
+        );
+        assert_eq!(frames.next(), None);
+
+        let frames: Vec<Frame> = animate(
+            &cargo,
+            &moves,
+            &mut CrateMover9000,
+            1,
+            InvalidMovePolicy::Clamp,
+        )
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(
+            frames.last().unwrap().cargo,
+            to_cargo(vec![vec![], vec!['B', 'A']])
+        );
+        assert_eq!(
+            frames
+                .iter()
+                .map(|x| x.errors.iter().map(|x| x.move_index).collect())
+                .collect::<Vec<Vec<usize>>>(),
+            vec![vec![], vec![0], vec![1]]
+        );
+    }
+
+    #[test]
+    fn test_move_display() {
+        let crane_move = Move {
+            amount: 3,
+            from: 0,
+            to: 11,
+        };
+        assert_eq!(crane_move.to_string(), "move 3 from 1 to 12");
+    }
+
+    #[test]
+    fn test_ship_display() {
+        let ship = Ship::new(to_cargo(vec![
+            vec!['Z', 'N'],
+            vec!['M', 'C', 'D'],
+            vec!['P'],
+        ]));
+        assert_eq!(
+            ship.to_string(),
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+    }
+
+    #[test]
+    fn test_parse_print_round_trip() {
+        let mut rng = Lcg::new(42);
+        let mut next = |max: usize| rng.below(max);
+
+        for n_stacks in [1, 3, 9, 10, 12, 25] {
+            let cargo: Cargo = (0..n_stacks)
+                .map(|_| {
+                    (0..next(8))
+                        .map(|_| ((b'A' + next(26) as u8) as char).to_string())
+                        .collect()
+                })
+                .collect();
+            let moves: Vec<Move> = (0..next(5))
+                .map(|_| Move {
+                    amount: next(12) + 1,
+                    from: next(n_stacks),
+                    to: next(n_stacks),
+                })
+                .collect();
+
+            let printed = format!(
+                "{}\n\n{}",
+                Ship::new(cargo.clone()),
+                moves
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            );
+            let parsed = parse_input(&printed);
+            assert_eq!(parsed, (cargo.clone(), moves.clone()));
+            assert_eq!(
+                format!("{}", Ship::new(parsed.0)),
+                format!("{}", Ship::new(cargo))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_cargo_with_many_stacks() {
+        let input = r#"                                        [K]
+[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [L]
+ 1   2   3   4   5   6   7   8   9  10  11 "#;
+        let mut expected: Vec<Vec<char>> = "ABCDEFGHIJL".chars().map(|x| vec![x]).collect();
+        expected[10].push('K');
+        assert_eq!(parse_cargo(input), Ok(to_cargo(expected)));
+    }
+
+    #[test]
+    fn test_parse_cargo_with_wide_labels() {
+        let input = r#"      [XYZ]
+[AB]  [C]   [D]
+ 1     2     3  "#;
+        assert_eq!(
+            parse_cargo(input),
+            Ok(vec![
+                vec!["AB".to_string()],
+                vec!["C".to_string(), "XYZ".to_string()],
+                vec!["D".to_string()],
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_cargo_errors() {
+        assert_eq!(
+            try_parse_input("[A] [B]\n\nmove 1 from 1 to 2"),
+            Err(ParseError {
+                line: 1,
+                token: "[A] [B]".to_string(),
+                kind: ParseErrorKind::MissingFooter
+            })
+        );
+        assert_eq!(
+            parse_cargo(""),
+            Err(ParseError {
+                line: 1,
+                token: "".to_string(),
+                kind: ParseErrorKind::MissingFooter
+            })
+        );
+        assert_eq!(
+            parse_cargo("    [C]\n[ ] [B]\n 1   2 "),
+            Err(ParseError {
+                line: 2,
+                token: "[ ]".to_string(),
+                kind: ParseErrorKind::EmptyLabel
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_cargo_with_wide_labels() {
+        let cargo = vec![
+            vec!["AB".to_string()],
+            vec!["C".to_string(), "XYZ".to_string()],
+            vec![],
+        ];
+        let drawing = render_cargo(&cargo);
+        assert_eq!(
+            drawing,
+            "      [XYZ]      \n[AB]   [C]       \n  1     2     3  "
+        );
+        assert_eq!(parse_cargo(&drawing), Ok(cargo.clone()));
+
+        let ship = Ship::new(cargo);
+        assert_eq!(ship.top_cargo(), "ABXYZ ");
+    }
+
+    #[test]
+    fn test_move_validate() {
+        let cargo = to_cargo(vec![vec!['A', 'B'], vec![]]);
+        let crane_move = |amount, from, to| Move { amount, from, to };
+
+        assert_eq!(crane_move(2, 0, 1).validate(&cargo), Ok(()));
+        assert_eq!(crane_move(0, 1, 0).validate(&cargo), Ok(()));
+        assert_eq!(
+            crane_move(3, 0, 1).validate(&cargo),
+            Err(InvalidMove::NotEnoughCrates {
+                requested: 3,
+                available: 2
+            })
+        );
+        assert_eq!(
+            crane_move(1, 0, 2).validate(&cargo),
+            Err(InvalidMove::StackOutOfRange {
+                stack: 2,
+                stacks: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_ship_try_operate() {
+        let cargo = to_cargo(vec![vec!['A', 'B', 'C'], vec![]]);
+        let moves = vec![
+            Move {
+                amount: 1,
+                from: 0,
+                to: 1,
+            },
+            Move {
+                amount: 5,
+                from: 0,
+                to: 1,
+            },
+            Move {
+                amount: 1,
+                from: 4,
+                to: 0,
+            },
+        ];
+        let not_enough = SimulationError {
+            move_index: 1,
+            reason: InvalidMove::NotEnoughCrates {
+                requested: 5,
+                available: 2,
+            },
+        };
+        let out_of_range = SimulationError {
+            move_index: 2,
+            reason: InvalidMove::StackOutOfRange {
+                stack: 4,
+                stacks: 2,
+            },
+        };
+
+        let mut ship = Ship::new(cargo.clone());
+        let res = ship.try_operate(&mut CrateMover9001, &moves, InvalidMovePolicy::Fail);
+        assert_eq!(res, Err(not_enough));
+        assert_eq!(
+            not_enough.to_string(),
+            "move #2: cannot move 5 crates from a stack with 2"
+        );
+        assert_eq!(ship.top_cargo(), "BC");
+
+        let mut ship = Ship::new(cargo.clone());
+        let res = ship.try_operate(&mut CrateMover9001, &moves, InvalidMovePolicy::Skip);
+        assert_eq!(res, Ok(vec![not_enough, out_of_range]));
+        assert_eq!(ship.top_cargo(), "BC");
+
+        let mut ship = Ship::new(cargo);
+        let res = ship.try_operate(&mut CrateMover9001, &moves, InvalidMovePolicy::Clamp);
+        assert_eq!(res, Ok(vec![not_enough, out_of_range]));
+        assert_eq!(ship.cargo(), &to_cargo(vec![vec![], vec!['C', 'A', 'B']]));
+    }
+
+    #[test]
+    fn test_lift_to_same_stack() {
+        let mut ship = Ship::new(to_cargo(vec![vec!['A', 'B']]));
+        ship.lift(0, 0, 2, true);
+        assert_eq!(ship.cargo(), &to_cargo(vec![vec!['A', 'B']]));
+    }
+
+    #[test]
+    fn test_custom_crane() {
+        // only uses the public API, like a crane model defined outside this module would
+        struct TopOnlyCrane;
+
+        impl Crane for TopOnlyCrane {
+            fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+                ship.lift(
+                    crane_move.from(),
+                    crane_move.to(),
+                    crane_move.amount().min(1),
+                    true,
+                );
+            }
+        }
+
+        let mut ship = Ship::new(to_cargo(vec![vec!['A', 'B'], vec![]]));
+        ship.operate(&mut TopOnlyCrane, &[Move::new(2, 0, 1)]);
+        assert_eq!(ship.cargo(), &to_cargo(vec![vec!['A'], vec!['B']]));
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let start = to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+        let target = to_cargo(vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']]);
+
+        let plan = plan_moves(&start, &target, &CrateMover9000, 10).unwrap();
+        assert_eq!(plan.len(), 3);
+        let mut ship = Ship::new(start.clone());
+        ship.operate(&mut CrateMover9000, &plan);
+        assert_eq!(ship.cargo(), &target);
+
+        let target = to_cargo(vec![vec!['Z', 'N', 'C', 'D'], vec!['M'], vec!['P']]);
+        let plan = plan_moves(&start, &target, &CrateMover9001, 10).unwrap();
+        assert_eq!(
+            plan,
+            vec![Move {
+                amount: 2,
+                from: 1,
+                to: 0
+            }]
+        );
+        assert_eq!(plan_moves(&start, &start, &CrateMover9001, 0), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_plan_moves_only_losing_crates() {
+        let start = to_cargo(vec![vec!['A', 'B'], vec![]]);
+        let target = to_cargo(vec![vec!['A'], vec!['B']]);
+        assert_eq!(misplaced_heuristic(&start, &target), 1);
+
+        let start = to_cargo(vec![vec!['A', 'B', 'C'], vec![], vec![]]);
+        let target = to_cargo(vec![vec!['A'], vec!['B'], vec!['C']]);
+        assert_eq!(misplaced_heuristic(&start, &target), 2);
+        let plan = plan_moves(&start, &target, &CrateMover9000, 2).unwrap();
+        assert_eq!(plan.len(), 2);
+        let mut ship = Ship::new(start);
+        ship.operate(&mut CrateMover9000, &plan);
+        assert_eq!(ship.cargo(), &target);
+    }
+
+    #[test]
+    fn test_plan_moves_errors() {
+        let start = to_cargo(vec![vec!['A', 'B'], vec![]]);
+
+        assert_eq!(
+            plan_moves(&start, &to_cargo(vec![vec!['A', 'B']]), &CrateMover9000, 5),
+            Err(PlanError::MismatchedStacks { from: 2, to: 1 })
+        );
+        assert_eq!(
+            plan_moves(
+                &start,
+                &to_cargo(vec![vec!['A'], vec!['C']]),
+                &CrateMover9000,
+                5
+            ),
+            Err(PlanError::MismatchedCrates)
+        );
+        assert_eq!(
+            plan_moves(
+                &start,
+                &to_cargo(vec![vec![], vec!['A', 'B']]),
+                &CrateMover9000,
+                1
+            ),
+            Err(PlanError::NotFound { max_moves: 1 })
+        );
+        assert_eq!(
+            plan_moves(
+                &start,
+                &to_cargo(vec![vec![], vec!['A', 'B']]),
+                &CrateMover9001,
+                1
+            )
+            .map(|x| x.len()),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_bulk_cranes_match_per_crate_references() {
+        // crates go one by one through a spare stack, so they are reversed twice and keep their
+        // order, without going through `Ship::lift`
+        struct PerCrateMover9001;
+
+        impl Crane for PerCrateMover9001 {
+            fn operate(&mut self, ship: &mut Ship, crane_move: &Move) {
+                let mut spare = vec![];
+                for _ in 0..crane_move.amount {
+                    spare.push(ship.cargo[crane_move.from].pop().unwrap());
+                }
+                while let Some(cargo_crate) = spare.pop() {
+                    ship.cargo[crane_move.to].push(cargo_crate);
+                }
+            }
+        }
+
+        let mut rng = Lcg::new(7);
+        let mut next = |max: usize| rng.below(max);
+
+        let stacks = 9;
+        let cargo: Cargo = (0..stacks)
+            .map(|i| (0..500).map(|j| format!("{}-{}", i, j)).collect())
+            .collect();
+        let mut sizes: Vec<usize> = vec![500; stacks];
+        let moves: Vec<Move> = (0..5000)
+            .map(|_| {
+                let candidate = next(stacks);
+                let from = if sizes[candidate] > 0 {
+                    candidate
+                } else {
+                    (0..stacks).find(|&i| sizes[i] > 0).unwrap()
+                };
+                let to = next(stacks);
+                let amount = next(sizes[from]) + 1;
+                sizes[from] -= amount;
+                sizes[to] += amount;
+                Move { amount, from, to }
+            })
+            .collect();
+        let input = (cargo, moves);
+
+        let run = |crane: &mut dyn Crane| {
+            let mut ship = Ship::new(input.0.clone());
+            ship.operate(crane, &input.1);
+            ship.cargo
+        };
+        assert_eq!(run(&mut BulkCrateMover9000), run(&mut CrateMover9000));
+        assert_eq!(run(&mut CrateMover9001), run(&mut PerCrateMover9001));
+        assert_eq!(
+            solve_with_crane(&input, crane_from_name("9000-bulk").unwrap().as_mut()),
+            solve_part1(&input)
+        );
+    }
+
+    #[test]
+    fn test_ship_history_rejects_invalid_moves() {
+        let cargo = to_cargo(vec![vec!['A', 'B'], vec![]]);
+        let mut history = ShipHistory::new(Ship::new(cargo.clone()), CrateMover9001);
+        history.apply(Move::new(1, 0, 1)).unwrap();
+        history.undo();
+
+        assert_eq!(
+            history.apply(Move::new(3, 0, 1)),
+            Err(InvalidMove::NotEnoughCrates {
+                requested: 3,
+                available: 2
+            })
+        );
+        // the undone move can still be redone
+        assert_eq!(history.moves().len(), 1);
+        assert_eq!(history.position(), 0);
+        assert_eq!(
+            history.apply_all(&[Move::new(1, 0, 1), Move::new(1, 4, 0)]),
+            Err(SimulationError {
+                move_index: 1,
+                reason: InvalidMove::StackOutOfRange {
+                    stack: 4,
+                    stacks: 2
+                }
+            })
+        );
+        assert_eq!(history.moves(), &[Move::new(1, 0, 1)]);
+        assert_eq!(history.position(), 1);
+        assert_eq!(history.state_at(0), Some(Ship::new(cargo)));
+    }
+
+    #[test]
+    fn test_ship_history_undo_redo() {
+        let cargo = to_cargo(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+        let moves = parse_moves(
+            "move 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2",
+            1,
+        )
+        .unwrap();
+        let mut history = ShipHistory::new(Ship::new(cargo.clone()), CrateMover9000);
+        history.apply_all(&moves).unwrap();
+        assert_eq!(history.ship().top_cargo(), "CMZ");
+
+        assert_eq!(history.undo(), Some(moves[3]));
+        assert_eq!(history.undo(), Some(moves[2]));
+        assert_eq!(history.position(), 2);
+        assert_eq!(history.ship().top_cargo(), " CZ");
+        assert_eq!(history.redo(), Some(moves[2]));
+        assert_eq!(history.ship().top_cargo(), "M Z");
+
+        history.apply(moves[3]).unwrap();
+        assert_eq!(history.moves().len(), 4);
+        assert_eq!(history.ship().top_cargo(), "CMZ");
+        assert_eq!(history.redo(), None);
+
+        history.seek(0);
+        assert_eq!(history.ship().cargo(), &cargo);
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn test_ship_history_state_at() {
+        let cargo = to_cargo(vec![vec!['A', 'B', 'C'], vec![]]);
+        let mut history = ShipHistory::new(Ship::new(cargo), InvertingCrane::default());
+        let moves: Vec<Move> = (0..200)
+            .map(|i| Move {
+                amount: 3,
+                from: i % 2,
+                to: (i + 1) % 2,
+            })
+            .collect();
+        history.apply_all(&moves).unwrap();
+
+        let mut reference = Ship::new(to_cargo(vec![vec!['A', 'B', 'C'], vec![]]));
+        let mut crane = InvertingCrane::default();
+        for (i, crane_move) in moves.iter().enumerate() {
+            assert_eq!(history.state_at(i).unwrap(), reference);
+            crane.operate(&mut reference, crane_move);
+        }
+        assert_eq!(history.state_at(200).unwrap(), reference);
+        assert_eq!(history.state_at(201), None);
+
+        history.seek(130);
+        history.apply(moves[0]).unwrap();
+        assert_eq!(history.moves().len(), 131);
+
+        let mut expected = ShipHistory::new(
+            Ship::new(to_cargo(vec![vec!['A', 'B', 'C'], vec![]])),
+            InvertingCrane::default(),
+        );
+        expected.apply_all(&moves[..130]).unwrap();
+        expected.apply(moves[0]).unwrap();
+        assert_eq!(history.state_at(131), expected.state_at(131));
+        assert_eq!(history.ship(), expected.ship());
+    }
+
+    #[test]
+    fn test_ship_history_diff() {
+        let cargo = to_cargo(vec![vec!['A', 'A'], vec!['B'], vec![]]);
+        let mut history = ShipHistory::new(Ship::new(cargo), CrateMover9000);
+        history
+            .apply_all(&parse_moves("move 2 from 1 to 3\nmove 1 from 2 to 1", 1).unwrap())
+            .unwrap();
+
+        let changes = history.diff(0, 2).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                CrateChange {
+                    label: "B".to_string(),
+                    from: (1, 0),
+                    to: (0, 0)
+                },
+                CrateChange {
+                    label: "A".to_string(),
+                    from: (0, 1),
+                    to: (2, 0)
+                },
+                CrateChange {
+                    label: "A".to_string(),
+                    from: (0, 0),
+                    to: (2, 1)
+                },
+            ]
+        );
+        assert_eq!(
+            changes[0].to_string(),
+            "[B] stack 2 level 1 -> stack 1 level 1"
+        );
+        assert_eq!(
+            history.diff(2, 1).unwrap(),
+            vec![CrateChange {
+                label: "B".to_string(),
+                from: (0, 0),
+                to: (1, 0)
+            }]
+        );
+        assert_eq!(history.diff(0, 3), None);
+    }
 }
@@ -1,51 +1,241 @@
 use aoc_runner_derive::aoc;
-use itertools::Itertools;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, Read};
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
 
 const PACKET_BUFFER_SIZE: usize = 4;
 const MESSAGE_BUFFER_SIZE: usize = 14;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct RingBuffer<T: Copy + PartialEq, const SIZE: usize> {
-    buffer: [Option<T>; SIZE],
-    write_ptr: usize,
+// Fixed-capacity FIFO queue. Pushing into a full buffer overwrites its oldest item.
+#[derive(Clone, Copy)]
+pub struct RingBuffer<T: Copy + Default, const SIZE: usize> {
+    buffer: [T; SIZE],
+    head: usize,
+    len: usize,
 }
 
-impl<const SIZE: usize, T: Copy + PartialEq> RingBuffer<T, SIZE> {
+impl<const SIZE: usize, T: Copy + Default> Default for RingBuffer<T, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize, T: Copy + Default> RingBuffer<T, SIZE> {
     pub fn new() -> Self {
         Self {
-            buffer: [None; SIZE],
-            write_ptr: 0,
+            buffer: [T::default(); SIZE],
+            head: 0,
+            len: 0,
         }
     }
 
+    pub const fn capacity(&self) -> usize {
+        SIZE
+    }
+
     pub fn is_full(&self) -> bool {
-        !self.buffer.iter().contains(&None)
+        self.len == SIZE
     }
 
     pub fn len(&self) -> usize {
-        if self.is_full() {
-            SIZE
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn slot(&self, index: usize) -> usize {
+        (self.head + index) % SIZE
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(&self.buffer[self.slot(index)])
         } else {
-            self.write_ptr
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            let slot = self.slot(index);
+            Some(&mut self.buffer[slot])
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, SIZE> {
+        Iter {
+            ring: self,
+            front: 0,
+            back: self.len,
         }
     }
 
     pub fn to_vec(&self) -> Vec<T> {
-        let res = if self.is_full() {
-            let head = self.buffer[self.write_ptr..].to_owned();
-            let tail = self.buffer[..self.write_ptr].to_owned();
-            [head, tail].concat()
+        self.iter().copied().collect()
+    }
+
+    // Returns the item that got overwritten, if the buffer was full
+    pub fn push(&mut self, x: T) -> Option<T> {
+        if SIZE == 0 {
+            return Some(x);
+        }
+
+        if self.is_full() {
+            let old = std::mem::replace(&mut self.buffer[self.head], x);
+            self.head = self.slot(1);
+            Some(old)
         } else {
-            self.buffer.to_vec()
-        };
+            let slot = self.slot(self.len);
+            self.buffer[slot] = x;
+            self.len += 1;
+            None
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let x = self.buffer[self.head];
+        self.head = self.slot(1);
+        self.len -= 1;
+        Some(x)
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+}
+
+impl<const SIZE: usize, T: Copy + Default + fmt::Debug> fmt::Debug for RingBuffer<T, SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<const SIZE: usize, T: Copy + Default + PartialEq> PartialEq for RingBuffer<T, SIZE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<const SIZE: usize, T: Copy + Default> Index<usize> for RingBuffer<T, SIZE> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("ring buffer index out of bounds")
+    }
+}
+
+impl<const SIZE: usize, T: Copy + Default> IndexMut<usize> for RingBuffer<T, SIZE> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index)
+            .expect("ring buffer index out of bounds")
+    }
+}
+
+impl<const SIZE: usize, T: Copy + Default> Extend<T> for RingBuffer<T, SIZE> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
 
-        res.iter().filter_map(|&x| x).collect()
+impl<const SIZE: usize, T: Copy + Default> FromIterator<T> for RingBuffer<T, SIZE> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
     }
+}
+
+pub struct Iter<'a, T: Copy + Default, const SIZE: usize> {
+    ring: &'a RingBuffer<T, SIZE>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T: Copy + Default, const SIZE: usize> Iterator for Iter<'a, T, SIZE> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let x = self.ring.get(self.front);
+        self.front += 1;
+        x
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
 
-    pub fn push(&mut self, x: T) {
-        self.buffer[self.write_ptr] = Some(x);
-        self.write_ptr = (self.write_ptr + 1) % SIZE;
+impl<T: Copy + Default, const SIZE: usize> DoubleEndedIterator for Iter<'_, T, SIZE> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.ring.get(self.back)
+    }
+}
+
+impl<T: Copy + Default, const SIZE: usize> ExactSizeIterator for Iter<'_, T, SIZE> {}
+
+impl<'a, T: Copy + Default, const SIZE: usize> IntoIterator for &'a RingBuffer<T, SIZE> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, SIZE>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T: Copy + Default, const SIZE: usize> {
+    ring: RingBuffer<T, SIZE>,
+}
+
+impl<T: Copy + Default, const SIZE: usize> Iterator for IntoIter<T, SIZE> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ring.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ring.len(), Some(self.ring.len()))
+    }
+}
+
+impl<T: Copy + Default, const SIZE: usize> ExactSizeIterator for IntoIter<T, SIZE> {}
+
+impl<T: Copy + Default, const SIZE: usize> IntoIterator for RingBuffer<T, SIZE> {
+    type Item = T;
+    type IntoIter = IntoIter<T, SIZE>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { ring: self }
     }
 }
 
@@ -61,7 +251,7 @@ impl<const T: usize> ElvenBuffer<T> for RingBuffer<char, T> {
     }
 }
 
-fn get_start_of_marker_for_signal<const T: usize>(
+pub fn get_start_of_marker_for_signal<const T: usize>(
     signal: &str,
     buffer: &mut RingBuffer<char, T>,
 ) -> usize {
@@ -75,16 +265,441 @@ fn get_start_of_marker_for_signal<const T: usize>(
     signal.len()
 }
 
+// Sliding window that keeps how many times each symbol appears in it, and how many symbols are
+// repeated, so checking for a marker after every new symbol is O(1)
+#[derive(Debug, Clone)]
+pub struct MarkerDetector<T = char> {
+    window: usize,
+    history: VecDeque<T>,
+    counts: HashMap<T, usize>,
+    duplicates: usize,
+}
+
+impl<T: Eq + Hash> PartialEq for MarkerDetector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.window == other.window && self.history == other.history
+    }
+}
+
+impl<T: Copy + Eq + Hash> MarkerDetector<T> {
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "marker window must not be empty");
+        Self {
+            window,
+            history: VecDeque::with_capacity(window + 1),
+            counts: HashMap::new(),
+            duplicates: 0,
+        }
+    }
+
+    // Returns whether the last `window` symbols (this one included) are all different
+    pub fn push(&mut self, x: T) -> bool {
+        let count = self.counts.entry(x).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            self.duplicates += 1;
+        }
+        self.history.push_back(x);
+
+        if self.history.len() > self.window {
+            let old = self.history.pop_front().unwrap();
+            let count = self.counts.get_mut(&old).unwrap();
+            *count -= 1;
+            if *count == 1 {
+                self.duplicates -= 1;
+            }
+        }
+
+        self.history.len() == self.window && self.duplicates == 0
+    }
+}
+
+// Like `get_start_of_marker_for_signal`, falls back to the signal length when there's no marker
+pub fn find_marker(signal: &str, window: usize) -> usize {
+    first_marker(signal, window).unwrap_or(signal.len())
+}
+
+pub fn first_marker(signal: &str, window: usize) -> Option<usize> {
+    markers(signal, window).next()
+}
+
+// Every position (amount of characters read, as in the puzzle) where a marker ends
+pub fn markers(signal: &str, window: usize) -> Markers<'_> {
+    Markers {
+        chars: signal.chars(),
+        detector: MarkerDetector::new(window),
+        position: 0,
+    }
+}
+
+pub struct Markers<'a> {
+    chars: std::str::Chars<'a>,
+    detector: MarkerDetector,
+    position: usize,
+}
+
+impl Iterator for Markers<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for x in self.chars.by_ref() {
+            self.position += 1;
+            if self.detector.push(x) {
+                return Some(self.position);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Run {
+    // 0-based index of the first character
+    pub start: usize,
+    pub len: usize,
+}
+
+// Longest stretch of the signal without repeated characters (the first one, if there's a tie)
+pub fn longest_distinct_run(signal: &str) -> Option<Run> {
+    let mut last_seen: HashMap<char, usize> = HashMap::new();
+    let mut best: Option<Run> = None;
+    let mut start = 0;
+
+    for (i, x) in signal.chars().enumerate() {
+        if let Some(&previous) = last_seen.get(&x) {
+            start = start.max(previous + 1);
+        }
+        last_seen.insert(x, i);
+
+        let len = i + 1 - start;
+        if best.is_none_or(|run| len > run.len) {
+            best = Some(Run { start, len });
+        }
+    }
+
+    best
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Alphabet {
+    Lowercase,
+    // the signal is read byte by byte, and marker positions are byte offsets
+    Bytes,
+    Unicode,
+    Custom(String),
+}
+
+const UNICODE_SCALAR_VALUES: usize = 0x110000 - 0x800;
+
+impl Alphabet {
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Lowercase => 26,
+            Self::Bytes => 256,
+            Self::Unicode => UNICODE_SCALAR_VALUES,
+            Self::Custom(symbols) => symbols.chars().collect::<HashSet<char>>().len(),
+        }
+    }
+
+    fn contains(&self, x: char) -> bool {
+        match self {
+            Self::Lowercase => x.is_ascii_lowercase(),
+            Self::Bytes | Self::Unicode => true,
+            Self::Custom(symbols) => symbols.contains(x),
+        }
+    }
+}
+
+// Alphabets as named in the CLI: `lowercase`, `bytes`, `unicode` and `custom:<symbols>`
+impl FromStr for Alphabet {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.split_once(':') {
+            Some(("custom", symbols)) => Ok(Self::Custom(symbols.to_string())),
+            _ => match raw {
+                "lowercase" => Ok(Self::Lowercase),
+                "bytes" => Ok(Self::Bytes),
+                "unicode" => Ok(Self::Unicode),
+                _ => Err(format!("unknown alphabet: {}", raw)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MarkerError {
+    EmptyWindow,
+    WindowTooLarge { window: usize, alphabet_size: usize },
+    // `position` is 0-based, in characters (or bytes for the `Bytes` alphabet)
+    InvalidSymbol { position: usize, symbol: char },
+}
+
+impl fmt::Display for MarkerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyWindow => write!(f, "marker window must not be empty"),
+            Self::WindowTooLarge {
+                window,
+                alphabet_size,
+            } => write!(
+                f,
+                "a window of {} can't have distinct symbols with an alphabet of {}",
+                window, alphabet_size
+            ),
+            Self::InvalidSymbol { position, symbol } => {
+                write!(
+                    f,
+                    "symbol {:?} at {} is not in the alphabet",
+                    symbol, position
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MarkerSpec {
+    window: usize,
+    alphabet: Alphabet,
+}
+
+impl MarkerSpec {
+    pub fn new(window: usize, alphabet: Alphabet) -> Result<Self, MarkerError> {
+        if window == 0 {
+            return Err(MarkerError::EmptyWindow);
+        }
+        if window > alphabet.size() {
+            return Err(MarkerError::WindowTooLarge {
+                window,
+                alphabet_size: alphabet.size(),
+            });
+        }
+        Ok(Self { window, alphabet })
+    }
+
+    pub fn packet() -> Self {
+        Self::new(PACKET_BUFFER_SIZE, Alphabet::Lowercase).unwrap()
+    }
+
+    pub fn message() -> Self {
+        Self::new(MESSAGE_BUFFER_SIZE, Alphabet::Lowercase).unwrap()
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    fn validate(&self, signal: &str) -> Result<(), MarkerError> {
+        match signal
+            .chars()
+            .enumerate()
+            .find(|(_, x)| !self.alphabet.contains(*x))
+        {
+            Some((position, symbol)) => Err(MarkerError::InvalidSymbol { position, symbol }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn markers(&self, signal: &str) -> Result<Vec<usize>, MarkerError> {
+        self.validate(signal)?;
+        Ok(match self.alphabet {
+            Alphabet::Bytes => {
+                let mut detector = MarkerDetector::new(self.window);
+                signal
+                    .bytes()
+                    .enumerate()
+                    .filter(|(_, x)| detector.push(*x))
+                    .map(|(i, _)| i + 1)
+                    .collect()
+            }
+            _ => markers(signal, self.window).collect(),
+        })
+    }
+
+    pub fn first_marker(&self, signal: &str) -> Result<Option<usize>, MarkerError> {
+        self.validate(signal)?;
+        Ok(match self.alphabet {
+            Alphabet::Bytes => {
+                let mut detector = MarkerDetector::new(self.window);
+                signal.bytes().position(|x| detector.push(x)).map(|i| i + 1)
+            }
+            _ => first_marker(signal, self.window),
+        })
+    }
+}
+
+// Decodes a signal that arrives in chunks, looking for both markers at once
+#[derive(Debug, Clone)]
+pub struct MarkerDecoder {
+    offset: usize,
+    packet: MarkerDetector<u8>,
+    message: MarkerDetector<u8>,
+    start_of_packet: Option<usize>,
+    start_of_message: Option<usize>,
+    // the signal is a single line, like the puzzle input
+    ended: bool,
+}
+
+impl Default for MarkerDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkerDecoder {
+    pub fn new() -> Self {
+        Self {
+            offset: 0,
+            packet: MarkerDetector::new(PACKET_BUFFER_SIZE),
+            message: MarkerDetector::new(MESSAGE_BUFFER_SIZE),
+            start_of_packet: None,
+            start_of_message: None,
+            ended: false,
+        }
+    }
+
+    // Bytes fed so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn start_of_packet(&self) -> Option<usize> {
+        self.start_of_packet
+    }
+
+    pub fn start_of_message(&self) -> Option<usize> {
+        self.start_of_message
+    }
+
+    // Whether there's nothing left to find: both markers are known, or the signal has ended
+    pub fn is_done(&self) -> bool {
+        self.ended || (self.start_of_packet.is_some() && self.start_of_message.is_some())
+    }
+
+    // Returns the start-of-message marker as soon as it's known.
+    // NOTE: offsets are absolute, counted from the first byte ever fed, and the
+    // .     detectors stop looking once both markers have been found or at the first newline,
+    // .     which ends the signal
+    pub fn feed(&mut self, chunk: &[u8]) -> Option<usize> {
+        let mut rest = chunk;
+        while let Some((&x, tail)) = rest.split_first() {
+            if self.is_done() {
+                break;
+            }
+            rest = tail;
+            self.offset += 1;
+            if x == b'\n' {
+                self.ended = true;
+                break;
+            }
+            if self.start_of_packet.is_none() && self.packet.push(x) {
+                self.start_of_packet = Some(self.offset);
+            }
+            if self.start_of_message.is_none() && self.message.push(x) {
+                self.start_of_message = Some(self.offset);
+            }
+        }
+        self.offset += rest.len();
+        self.start_of_message
+    }
+}
+
+const STREAM_CHUNK_SIZE: usize = 4096;
+
+// Feeds a whole stream through a decoder, stopping early once both markers are known
+pub fn decode<R: Read>(mut reader: R) -> io::Result<MarkerDecoder> {
+    let mut decoder = MarkerDecoder::new();
+    let mut chunk = [0; STREAM_CHUNK_SIZE];
+    while !decoder.is_done() {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => {
+                decoder.feed(&chunk[..n]);
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(decoder)
+}
+
+// Bitmask fast path: every lowercase letter is one bit of a `u32`, and a window is a
+// marker when its rolling XOR mask has a bit set for each of its symbols.
+// NOTE: a repeated letter toggles its bit off again, so any duplicate leaves fewer
+// .     than `window` bits set
+#[cfg(feature = "bitmask")]
+fn scan_bitmask(signal: &[u8], window: usize) -> Option<usize> {
+    let bit = |x: u8| 1u32 << (x - b'a');
+    let mut mask = 0u32;
+    for (i, &x) in signal.iter().enumerate() {
+        mask ^= bit(x);
+        if i >= window {
+            mask ^= bit(signal[i - window]);
+        }
+        if mask.count_ones() as usize == window {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+// Same results as `first_marker`, which it falls back to for anything but lowercase letters
+#[cfg(feature = "bitmask")]
+pub fn bitmask_first_marker(signal: &str, window: usize) -> Option<usize> {
+    assert!(window > 0, "marker window must not be empty");
+    if !signal.bytes().all(|x| x.is_ascii_lowercase()) {
+        return first_marker(signal, window);
+    }
+    if window > 26 {
+        return None;
+    }
+    scan_bitmask(signal.as_bytes(), window)
+}
+
+// Splits the signal into one chunk per thread, each of them starting `window - 1` symbols
+// early so markers straddling a boundary are still found by the chunk they end in
+#[cfg(feature = "parallel")]
+pub fn parallel_first_marker(signal: &str, window: usize, threads: usize) -> Option<usize> {
+    assert!(window > 0, "marker window must not be empty");
+    if threads <= 1 || !signal.bytes().all(|x| x.is_ascii_lowercase()) {
+        return bitmask_first_marker(signal, window);
+    }
+    if window > 26 {
+        return None;
+    }
+
+    let bytes = signal.as_bytes();
+    let chunk_size = bytes.len().div_ceil(threads).max(window);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..bytes.len())
+            .step_by(chunk_size)
+            .map(|start| {
+                let from = start.saturating_sub(window - 1);
+                let to = (start + chunk_size).min(bytes.len());
+                scope.spawn(move || scan_bitmask(&bytes[from..to], window).map(|i| from + i))
+            })
+            .collect();
+        // every chunk has to be joined anyway, so the first one in order wins
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap())
+            .next()
+    })
+}
+
 #[aoc(day6, part1)]
 pub fn solve_part1(input: &str) -> usize {
-    let mut buffer = RingBuffer::<char, PACKET_BUFFER_SIZE>::new();
-    get_start_of_marker_for_signal(input, &mut buffer)
+    find_marker(input, PACKET_BUFFER_SIZE)
 }
 
 #[aoc(day6, part2)]
 pub fn solve_part2(input: &str) -> usize {
-    let mut buffer = RingBuffer::<char, MESSAGE_BUFFER_SIZE>::new();
-    get_start_of_marker_for_signal(input, &mut buffer)
+    find_marker(input, MESSAGE_BUFFER_SIZE)
 }
 
 #[cfg(test)]
@@ -108,4 +723,353 @@ mod tests {
         assert_eq!(solve_part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
         assert_eq!(solve_part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
     }
+
+    #[test]
+    fn test_marker_detector() {
+        let mut detector = MarkerDetector::new(3);
+        let found: Vec<bool> = "abcaabbcd".chars().map(|x| detector.push(x)).collect();
+        assert_eq!(
+            found,
+            vec![false, false, true, true, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_find_marker_matches_ring_buffer() {
+        let signals = [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "nppdvjthqldpwncqszvftbrmjlhg",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+            "aaaaaaaaaaaaaaaaaaaa",
+        ];
+        for signal in signals {
+            assert_eq!(
+                find_marker(signal, PACKET_BUFFER_SIZE),
+                get_start_of_marker_for_signal(
+                    signal,
+                    &mut RingBuffer::<char, PACKET_BUFFER_SIZE>::new()
+                )
+            );
+            assert_eq!(
+                find_marker(signal, MESSAGE_BUFFER_SIZE),
+                get_start_of_marker_for_signal(
+                    signal,
+                    &mut RingBuffer::<char, MESSAGE_BUFFER_SIZE>::new()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_push() {
+        let mut ring = RingBuffer::<u8, 3>::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.capacity(), 3);
+
+        assert_eq!(ring.push(1), None);
+        assert_eq!(ring.push(2), None);
+        assert_eq!(ring.len(), 2);
+        assert!(!ring.is_full());
+
+        assert_eq!(ring.push(3), None);
+        assert!(ring.is_full());
+        assert_eq!(ring.push(4), Some(1));
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.to_vec(), vec![2, 3, 4]);
+        assert_eq!(ring.front(), Some(&2));
+        assert_eq!(ring.back(), Some(&4));
+    }
+
+    #[test]
+    fn test_ring_buffer_pop_front() {
+        let mut ring: RingBuffer<u8, 3> = [1, 2, 3, 4].into_iter().collect();
+        assert_eq!(ring.pop_front(), Some(2));
+        ring.push(5);
+        ring.push(6);
+        assert_eq!(ring.to_vec(), vec![4, 5, 6]);
+        assert_eq!(ring.pop_front(), Some(4));
+        assert_eq!(ring.pop_front(), Some(5));
+        assert_eq!(ring.pop_front(), Some(6));
+        assert_eq!(ring.pop_front(), None);
+        assert!(ring.is_empty());
+        assert_eq!(ring.front(), None);
+        assert_eq!(ring.back(), None);
+    }
+
+    #[test]
+    fn test_ring_buffer_indexing() {
+        let mut ring: RingBuffer<char, 4> = "abcdef".chars().collect();
+        assert_eq!(ring[0], 'c');
+        assert_eq!(ring[3], 'f');
+        assert_eq!(ring.get(4), None);
+
+        ring[1] = 'x';
+        assert_eq!(ring.to_vec(), vec!['c', 'x', 'e', 'f']);
+        *ring.get_mut(0).unwrap() = 'y';
+        assert_eq!(ring.to_vec(), vec!['y', 'x', 'e', 'f']);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ring_buffer_index_out_of_bounds() {
+        let ring: RingBuffer<u8, 4> = [1, 2].into_iter().collect();
+        let _ = ring[2];
+    }
+
+    #[test]
+    fn test_ring_buffer_iterators() {
+        let mut ring = RingBuffer::<u32, 3>::new();
+        ring.extend([1, 2, 3, 4, 5]);
+
+        assert_eq!(ring.iter().len(), 3);
+        assert_eq!(ring.iter().copied().collect::<Vec<u32>>(), vec![3, 4, 5]);
+        assert_eq!(
+            ring.iter().rev().copied().collect::<Vec<u32>>(),
+            vec![5, 4, 3]
+        );
+
+        let mut iter = ring.iter();
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next_back(), None);
+
+        let mut total = 0;
+        for x in &ring {
+            total += x;
+        }
+        assert_eq!(total, 12);
+
+        assert_eq!(ring.into_iter().collect::<Vec<u32>>(), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_ring_buffer_equality_and_debug() {
+        let mut a = RingBuffer::<u8, 3>::new();
+        a.extend([9, 1, 2, 3]);
+        let b: RingBuffer<u8, 3> = [1, 2, 3].into_iter().collect();
+        assert_eq!(a, b);
+        assert_eq!(format!("{:?}", a), "[1, 2, 3]");
+
+        a.clear();
+        assert!(a.is_empty());
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_ring_buffer_with_no_capacity() {
+        let mut ring = RingBuffer::<u8, 0>::new();
+        assert_eq!(ring.push(1), Some(1));
+        assert!(ring.is_empty());
+        assert!(ring.is_full());
+        assert_eq!(ring.pop_front(), None);
+    }
+
+    #[test]
+    fn test_markers() {
+        assert_eq!(
+            markers("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4)
+                .take(5)
+                .collect::<Vec<usize>>(),
+            vec![7, 8, 9, 10, 11]
+        );
+        assert_eq!(
+            markers("abcabbcdd", 3).collect::<Vec<usize>>(),
+            vec![3, 4, 5, 8]
+        );
+        assert_eq!(markers("aaaa", 2).count(), 0);
+    }
+
+    #[test]
+    fn test_first_marker() {
+        assert_eq!(first_marker("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), Some(5));
+        assert_eq!(first_marker("abcd", 4), Some(4));
+        assert_eq!(first_marker("abca", 4), None);
+        assert_eq!(find_marker("abca", 4), 4);
+    }
+
+    #[test]
+    fn test_longest_distinct_run() {
+        assert_eq!(
+            longest_distinct_run("abcabcbb"),
+            Some(Run { start: 0, len: 3 })
+        );
+        assert_eq!(
+            longest_distinct_run("pwwkew"),
+            Some(Run { start: 2, len: 3 })
+        );
+        assert_eq!(longest_distinct_run("bbbb"), Some(Run { start: 0, len: 1 }));
+        assert_eq!(longest_distinct_run(""), None);
+    }
+
+    #[test]
+    fn test_marker_spec_validation() {
+        assert_eq!(
+            MarkerSpec::new(0, Alphabet::Lowercase),
+            Err(MarkerError::EmptyWindow)
+        );
+        assert_eq!(
+            MarkerSpec::new(27, Alphabet::Lowercase),
+            Err(MarkerError::WindowTooLarge {
+                window: 27,
+                alphabet_size: 26
+            })
+        );
+        assert_eq!(
+            MarkerSpec::new(3, Alphabet::Custom("abab".to_string())),
+            Err(MarkerError::WindowTooLarge {
+                window: 3,
+                alphabet_size: 2
+            })
+        );
+        assert!(MarkerSpec::new(26, Alphabet::Lowercase).is_ok());
+        assert!(MarkerSpec::new(100, Alphabet::Bytes).is_ok());
+    }
+
+    #[test]
+    fn test_marker_spec_first_marker() {
+        assert_eq!(
+            MarkerSpec::packet().first_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
+            Ok(Some(7))
+        );
+        assert_eq!(
+            MarkerSpec::message().first_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
+            Ok(Some(19))
+        );
+        assert_eq!(
+            MarkerSpec::packet().first_marker("abcD"),
+            Err(MarkerError::InvalidSymbol {
+                position: 3,
+                symbol: 'D'
+            })
+        );
+
+        let spec = MarkerSpec::new(3, Alphabet::Unicode).unwrap();
+        assert_eq!(spec.first_marker("ññabc"), Ok(Some(4)));
+        let spec = MarkerSpec::new(3, Alphabet::Bytes).unwrap();
+        assert_eq!(spec.first_marker("ññabc"), Ok(Some(5)));
+        assert_eq!(spec.markers("aab1"), Ok(vec![4]));
+    }
+
+    #[test]
+    fn test_alphabet_from_str() {
+        assert_eq!("lowercase".parse(), Ok(Alphabet::Lowercase));
+        assert_eq!("bytes".parse(), Ok(Alphabet::Bytes));
+        assert_eq!("unicode".parse(), Ok(Alphabet::Unicode));
+        assert_eq!(
+            "custom:xyz".parse(),
+            Ok(Alphabet::Custom("xyz".to_string()))
+        );
+        assert!("greek".parse::<Alphabet>().is_err());
+    }
+
+    #[test]
+    fn test_marker_decoder_chunks() {
+        let signal = b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg";
+        for size in 1..=signal.len() {
+            let mut decoder = MarkerDecoder::new();
+            let mut found = None;
+            for chunk in signal.chunks(size) {
+                found = decoder.feed(chunk);
+            }
+            assert_eq!(decoder.start_of_packet(), Some(10));
+            assert_eq!(found, Some(29));
+            assert_eq!(decoder.offset(), signal.len());
+        }
+
+        let mut decoder = MarkerDecoder::default();
+        assert_eq!(decoder.feed(b"aaaabc"), None);
+        assert_eq!(decoder.start_of_packet(), None);
+        assert_eq!(decoder.feed(b"d"), None);
+        assert_eq!(decoder.start_of_packet(), Some(7));
+        assert!(!decoder.is_done());
+    }
+
+    #[test]
+    fn test_marker_decoder_stops_at_newline() {
+        let mut decoder = MarkerDecoder::new();
+        assert_eq!(decoder.feed(b"abc\nd"), None);
+        assert!(decoder.is_done());
+        assert_eq!(decoder.feed(b"efghijklmnopqr"), None);
+        assert_eq!(decoder.start_of_packet(), None);
+        assert_eq!(decoder.offset(), 19);
+
+        let decoder = decode("abc\n".as_bytes()).unwrap();
+        assert_eq!(
+            decoder.start_of_packet(),
+            first_marker("abc", PACKET_BUFFER_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_decode_reader() {
+        for signal in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        ] {
+            let decoder = decode(signal.as_bytes()).unwrap();
+            assert_eq!(decoder.start_of_packet(), Some(solve_part1(signal)));
+            assert_eq!(decoder.start_of_message(), Some(solve_part2(signal)));
+        }
+    }
+
+    #[cfg(feature = "bitmask")]
+    fn random_signal(len: usize, letters: usize, seed: u64) -> String {
+        let mut rng = crate::test_utils::Lcg::new(seed);
+        (0..len)
+            .map(|_| (b'a' + rng.below(letters) as u8) as char)
+            .collect()
+    }
+
+    #[cfg(feature = "bitmask")]
+    #[test]
+    fn test_bitmask_matches_ring_buffer() {
+        for (seed, letters) in (0..40).zip([4, 8, 14, 15, 20, 26].iter().cycle()) {
+            let signal = random_signal(500, *letters, seed);
+            for window in [1, 4, 14] {
+                assert_eq!(
+                    bitmask_first_marker(&signal, window),
+                    first_marker(&signal, window)
+                );
+            }
+            assert_eq!(
+                bitmask_first_marker(&signal, PACKET_BUFFER_SIZE).unwrap_or(signal.len()),
+                get_start_of_marker_for_signal(
+                    &signal,
+                    &mut RingBuffer::<char, PACKET_BUFFER_SIZE>::new()
+                )
+            );
+            assert_eq!(
+                bitmask_first_marker(&signal, MESSAGE_BUFFER_SIZE).unwrap_or(signal.len()),
+                get_start_of_marker_for_signal(
+                    &signal,
+                    &mut RingBuffer::<char, MESSAGE_BUFFER_SIZE>::new()
+                )
+            );
+        }
+        assert_eq!(bitmask_first_marker("abcdefghijklmnopqrstuvwxyz", 27), None);
+        assert_eq!(bitmask_first_marker("aaBCD", 3), Some(4));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_matches_bitmask() {
+        for (seed, letters) in (0..20).zip([14, 15, 20].iter().cycle()) {
+            let signal = random_signal(2000, *letters, seed);
+            for threads in [1, 2, 3, 7, 64] {
+                for window in [4, 14] {
+                    assert_eq!(
+                        parallel_first_marker(&signal, window, threads),
+                        bitmask_first_marker(&signal, window)
+                    );
+                }
+            }
+        }
+        // with 14-byte chunks the marker at 38..52 straddles the boundary at 42
+        let signal = format!("{}{}", "ab".repeat(20), "cdefghijklmnop");
+        assert_eq!(parallel_first_marker(&signal, 14, 4), Some(52));
+    }
 }
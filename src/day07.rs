@@ -67,9 +67,9 @@ impl FSTree {
         let mut tree = Arena::new();
         let root = tree.new_node(FSNode::Dir("/".to_string()));
         FSTree {
-            tree: tree,
+            tree,
             current: root,
-            root: root,
+            root,
         }
     }
 
@@ -114,7 +114,7 @@ impl FSTree {
         }
 
         if dir == ".." {
-            self.current = self.current.ancestors(&self.tree).skip(1).next().unwrap();
+            self.current = self.current.ancestors(&self.tree).nth(1).unwrap();
             return;
         }
 
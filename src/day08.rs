@@ -60,18 +60,14 @@ impl Forest for Grid<Tree> {
 
 #[aoc_generator(day8)]
 pub fn parse_input(input: &str) -> Grid<Tree> {
-    let (data, n_cols) = input
-        .lines()
-        .into_iter()
-        .fold((vec![], 0), |(mut data, _), line| {
-            let cells: Vec<u64> = line
-                .chars()
-                .into_iter()
-                .map(|x| x.to_string().parse::<u64>().unwrap())
-                .collect();
-            data.extend(cells);
-            (data, line.len())
-        });
+    let (data, n_cols) = input.lines().fold((vec![], 0), |(mut data, _), line| {
+        let cells: Vec<u64> = line
+            .chars()
+            .map(|x| x.to_string().parse::<u64>().unwrap())
+            .collect();
+        data.extend(cells);
+        (data, line.len())
+    });
 
     Grid::from_vec(data, n_cols)
 }
@@ -128,17 +124,17 @@ mod tests {
     #[test]
     fn test_forest_is_tree_visible() {
         let forest = input();
-        assert_eq!(forest.is_tree_visible(0, 0), true);
-        assert_eq!(forest.is_tree_visible(4, 4), true);
-        assert_eq!(forest.is_tree_visible(1, 1), true);
-        assert_eq!(forest.is_tree_visible(2, 1), true);
-        assert_eq!(forest.is_tree_visible(3, 1), false);
-        assert_eq!(forest.is_tree_visible(1, 2), true);
-        assert_eq!(forest.is_tree_visible(2, 2), false);
-        assert_eq!(forest.is_tree_visible(3, 2), true);
-        assert_eq!(forest.is_tree_visible(2, 3), true);
-        assert_eq!(forest.is_tree_visible(1, 3), false);
-        assert_eq!(forest.is_tree_visible(3, 3), false);
+        assert!(forest.is_tree_visible(0, 0));
+        assert!(forest.is_tree_visible(4, 4));
+        assert!(forest.is_tree_visible(1, 1));
+        assert!(forest.is_tree_visible(2, 1));
+        assert!(!forest.is_tree_visible(3, 1));
+        assert!(forest.is_tree_visible(1, 2));
+        assert!(!forest.is_tree_visible(2, 2));
+        assert!(forest.is_tree_visible(3, 2));
+        assert!(forest.is_tree_visible(2, 3));
+        assert!(!forest.is_tree_visible(1, 3));
+        assert!(!forest.is_tree_visible(3, 3));
     }
 
     #[test]
@@ -9,4 +9,7 @@ pub mod day06;
 pub mod day07;
 pub mod day08;
 
+#[cfg(test)]
+mod test_utils;
+
 aoc_lib! {year = 2022}
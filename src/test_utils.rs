@@ -0,0 +1,14 @@
+// Simple linear congruential generator, so randomized tests are deterministic
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    // A number in `0..max`
+    pub fn below(&mut self, max: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+        ((self.0 >> 33) % max as u64) as usize
+    }
+}